@@ -0,0 +1,211 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+//
+// 誤差拡散ディザ
+// コンピュートシェーダー（diffuse）で誤差を拡散しながら量子化した結果をストレージバッファに書き込み、
+// フラグメントシェーダー（blit）でバッファの内容を画面に描画する
+//
+
+struct ErrorDiffusionParams {
+    width:        u32
+    , height:     u32
+    , kernel:     u32
+    , serpentine: u32
+    , monochrome: u32
+    , _pad_0:     u32
+    , _pad_1:     u32
+    , _pad_2:     u32
+}
+
+// ErrorDiffusionParams.kernel の値
+const DIFFUSION_KERNEL_FLOYD_STEINBERG: u32     = 0u;
+const DIFFUSION_KERNEL_ATKINSON: u32            = 1u;
+const DIFFUSION_KERNEL_JARVIS_JUDICE_NINKE: u32 = 2u;
+
+//
+// 1つ上の行と走査方向が同じ行が何ピクセル遅れて処理を進めるか（ERROR_DIFFUSION_ROW_LAG と合わせること）
+// カーネルは左右2ピクセル・下2行まで誤差を配るため、5ピクセル遅らせれば
+// 同じステップで複数の行が同じピクセルに書き込むことはなく、参照時には誤差が確定している
+//
+const ROW_LAG: i32 = 5;
+
+// 1回のディスパッチで処理する行の範囲
+struct ErrorDiffusionBand {
+    first_row:   u32
+    , row_count: u32
+    , _pad_0:    u32
+    , _pad_1:    u32
+}
+
+@group(0) @binding(0) var source_texture: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> pixels: array<vec4<f32>>;
+@group(0) @binding(2) var<uniform> params: ErrorDiffusionParams;
+@group(0) @binding(3) var palette_texture: texture_2d<f32>; // 1行目が線形 RGB、2行目が OKLab（2色未満の場合は使わない）
+@group(0) @binding(4) var<uniform> band: ErrorDiffusionBand;
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low  = color * 12.92;
+    let high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low  = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return select(high, low, color <= vec3(0.04045));
+}
+
+//
+// 線形 sRGB から OKLab へ変換する（post_process.wgsl と同じ）
+//
+fn linear_srgb_to_oklab(c: vec3<f32>) -> vec3<f32> {
+    let l = 0.4122214708 * c.r + 0.5363325363 * c.g + 0.0514459929 * c.b;
+    let m = 0.2119034982 * c.r + 0.6806995451 * c.g + 0.1073969566 * c.b;
+    let s = 0.0883024619 * c.r + 0.2817188376 * c.g + 0.6299787005 * c.b;
+
+    let l_ = pow(max(l, 0.0), 1.0 / 3.0);
+    let m_ = pow(max(m, 0.0), 1.0 / 3.0);
+    let s_ = pow(max(s, 0.0), 1.0 / 3.0);
+
+    return vec3<f32>(
+        0.2104542553 * l_ + 0.7936177850 * m_ - 0.0040720468 * s_,
+        1.9779984951 * l_ - 2.4285922050 * m_ + 0.4505937099 * s_,
+        0.0259040371 * l_ + 0.7827717662 * m_ - 0.8086757660 * s_
+    );
+}
+
+//
+// OKLab 空間でパレット内の最も近い色を探し、誤差の計算と同じ sRGB の値で返す
+// 誤差が蓄積した値は 0.0～1.0 をはみ出すので、探す時だけ範囲内に収める
+//
+fn nearest_palette_color(color: vec3<f32>) -> vec3<f32> {
+    let palette_size = i32(textureDimensions(palette_texture).x);
+    let lab = linear_srgb_to_oklab(srgb_to_linear(clamp(color, vec3(0.0), vec3(1.0))));
+
+    var nearest = 0;
+    var nearest_distance = 1e10;
+    for (var i = 0; i < palette_size; i++) {
+        let diff = textureLoad(palette_texture, vec2<i32>(i, 1), 0).rgb - lab;
+        let distance = dot(diff, diff);
+        if distance < nearest_distance {
+            nearest = i;
+            nearest_distance = distance;
+        }
+    }
+    return linear_to_srgb(textureLoad(palette_texture, vec2<i32>(nearest, 0), 0).rgb);
+}
+
+// 白黒、パレット内の色、またはチャンネルごとの2値に量子化する
+fn quantize(color: vec3<f32>) -> vec3<f32> {
+    if params.monochrome == 1u {
+        let gray = dot(color, vec3(0.299, 0.587, 0.114));
+        return vec3(select(0.0, 1.0, gray >= 0.5));
+    }
+    if textureDimensions(palette_texture).x >= 2u {
+        return nearest_palette_color(color);
+    }
+    return select(vec3(0.0), vec3(1.0), color >= vec3(0.5));
+}
+
+// 未処理のピクセルに誤差を加算する（dx は走査方向に合わせて反転済み）
+fn spread(x: i32, y: i32, error: vec3<f32>, weight: f32) {
+    if x < 0 || x >= i32(params.width) || y >= i32(params.height) {
+        return;
+    }
+    let index = u32(y) * params.width + u32(x);
+    pixels[index] = vec4(pixels[index].rgb + error * weight, 0.0);
+}
+
+//
+// 1ピクセルを量子化して誤差をカーネルに従って周囲へ配る
+// バッファは処理前は蓄積された誤差、処理後は量子化後の色を持つ
+//
+fn diffuse_pixel(x: i32, y: i32, direction: i32) {
+    let index = u32(y) * params.width + u32(x);
+    // 誤差の計算はガンマ補正後（sRGB）の値で行う
+    // HDR のビューでは 1.0 を超える値が入るので、量子化の前に 0.0～1.0 に収める（量子化後の色は常にこの範囲になる）
+    let source = clamp(textureLoad(source_texture, vec2<i32>(x, y), 0).rgb, vec3(0.0), vec3(1.0));
+    let color = linear_to_srgb(source) + pixels[index].rgb;
+    let quantized = quantize(color);
+    let error = color - quantized;
+    pixels[index] = vec4(quantized, 1.0);
+
+    let d = direction;
+    switch params.kernel {
+        case DIFFUSION_KERNEL_ATKINSON: {
+            let w = 1.0 / 8.0;
+            spread(x + d,     y,     error, w);
+            spread(x + 2 * d, y,     error, w);
+            spread(x - d,     y + 1, error, w);
+            spread(x,         y + 1, error, w);
+            spread(x + d,     y + 1, error, w);
+            spread(x,         y + 2, error, w);
+        }
+        case DIFFUSION_KERNEL_JARVIS_JUDICE_NINKE: {
+            let w = 1.0 / 48.0;
+            spread(x + d,     y,     error, 7.0 * w);
+            spread(x + 2 * d, y,     error, 5.0 * w);
+            spread(x - 2 * d, y + 1, error, 3.0 * w);
+            spread(x - d,     y + 1, error, 5.0 * w);
+            spread(x,         y + 1, error, 7.0 * w);
+            spread(x + d,     y + 1, error, 5.0 * w);
+            spread(x + 2 * d, y + 1, error, 3.0 * w);
+            spread(x - 2 * d, y + 2, error, 1.0 * w);
+            spread(x - d,     y + 2, error, 3.0 * w);
+            spread(x,         y + 2, error, 5.0 * w);
+            spread(x + d,     y + 2, error, 3.0 * w);
+            spread(x + 2 * d, y + 2, error, 1.0 * w);
+        }
+        default: {
+            // Floyd–Steinberg
+            let w = 1.0 / 16.0;
+            spread(x + d, y,     error, 7.0 * w);
+            spread(x - d, y + 1, error, 3.0 * w);
+            spread(x,     y + 1, error, 5.0 * w);
+            spread(x + d, y + 1, error, 1.0 * w);
+        }
+    }
+}
+
+//
+// 1回のディスパッチで行のバンド（band.first_row から band.row_count 行）を処理する
+// 各スレッドが1行ずつ担当し、行ごとの開始ステップだけ遅れて進む（ウェーブフロント方式）
+// バンドの行数は1回のディスパッチのステップ数が上限を超えないように CPU 側で決める（error_diffusion_bands）
+//
+// 蛇行走査では1行ごとに走査方向を反転する
+// 逆向きの行の先頭は1つ上の行の末尾の誤差を待つ必要があるため、1つ上の行が終わるまで（width ステップ）遅らせる
+// ※ そのため蛇行走査では行が並列には進まず、バンドの行数も少なくなる
+//
+@compute @workgroup_size(256, 1, 1)
+fn diffuse(@builtin(local_invocation_id) local_id: vec3<u32>) {
+    let width = i32(params.width);
+    let serpentine = params.serpentine == 1u;
+
+    let row = i32(local_id.x);
+    let rows = i32(band.row_count);
+    let y = i32(band.first_row) + row;
+    let lag = select(ROW_LAG, width, serpentine);
+    let start = lag * row;
+    let steps = width + lag * (rows - 1);
+    // 奇数行を右から左へ走査する（バンドをまたいでも行の番号で決まる）
+    let reverse = serpentine && y % 2 == 1;
+    let direction = select(1, -1, reverse);
+    for (var step = 0; step < steps; step++) {
+        let i = step - start;
+        if row < rows && i >= 0 && i < width {
+            diffuse_pixel(select(i, width - 1 - i, reverse), y, direction);
+        }
+        storageBarrier();
+    }
+}
+
+@group(0) @binding(0) var<storage, read> result: array<vec4<f32>>;
+@group(0) @binding(1) var<uniform> blit_params: ErrorDiffusionParams;
+
+@fragment
+fn blit(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let coord = vec2<u32>(in.position.xy);
+    let index = min(coord.y, blit_params.height - 1u) * blit_params.width + min(coord.x, blit_params.width - 1u);
+    // 量子化後の色は sRGB の値なので線形に戻す
+    return vec4(srgb_to_linear(result[index].rgb), 1.0);
+}
@@ -0,0 +1,33 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+//
+// 色調補正のポストエフェクト（露出、コントラスト、彩度、色味）
+//
+
+struct Grading {
+    exposure:     f32
+    , contrast:   f32
+    , saturation: f32
+    , _pad_0:     f32
+    , tint:       vec4<f32> // 線形 RGB
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var<uniform> grading: Grading;
+
+// コントラストの中心にする明るさ（線形の 18% グレー）
+const MIDDLE_GRAY: f32 = 0.18;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(screen_texture, texture_sampler, in.uv);
+
+    var rgb = color.rgb * exp2(grading.exposure);
+    rgb = max((rgb - MIDDLE_GRAY) * grading.contrast + MIDDLE_GRAY, vec3(0.0));
+    let luminance = dot(rgb, vec3(0.2126, 0.7152, 0.0722));
+    rgb = max(mix(vec3(luminance), rgb, grading.saturation), vec3(0.0));
+    rgb *= grading.tint.rgb;
+
+    return vec4(rgb, color.a);
+}
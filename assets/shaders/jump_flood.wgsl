@@ -0,0 +1,115 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+//
+// ジャンプフラッディングによるアウトライン
+// init でマスクの ID があるピクセルを種にし、jump で 2^n, ..., 2, 1 ピクセル離れた 3x3 の種から
+// 最も近いものを伝播させる（1 パスごとに間隔を半分にする）
+// 最後に outline で最も近い種までの距離からアウトラインの濃さを求める
+//
+
+struct JumpFlood {
+    step:     u32 // このパスで参照する周囲のピクセルとの間隔
+    , glow:   f32 // アウトラインの外側に広がる光の長さ（ピクセル）
+    , _pad_0: u32
+    , _pad_1: u32
+}
+
+struct OutlineEntry {
+    color:    vec4<f32> // 線形 RGB、a は合成の強さ
+    , width:  f32
+    , _pad_0: f32
+    , _pad_1: f32
+    , _pad_2: f32
+}
+
+// MAX_OUTLINED_OBJECTS と同じ長さにすること
+const MAX_OUTLINED_OBJECTS: u32 = 256u;
+
+struct OutlineSettings {
+    count:       u32
+    , max_width: f32
+    , _pad_0:    u32
+    , _pad_1:    u32
+    , objects:   array<OutlineEntry, MAX_OUTLINED_OBJECTS> // ID - 1 番目がそのオブジェクトの設定
+}
+
+// 種がないことを表す座標（座標は Rg32Uint に書き込むので画面の大きさによらず使われない値になる）
+const NO_SEED: u32 = 0xffffffffu;
+
+// エントリーポイントごとに使うバインディングだけがパイプラインのレイアウトに含まれる
+@group(0) @binding(0) var mask_texture: texture_2d<u32>;        // init, outline
+@group(0) @binding(1) var seed_texture: texture_2d<u32>;        // jump, outline
+@group(0) @binding(2) var<uniform> jump_flood: JumpFlood;       // jump, outline
+@group(0) @binding(3) var screen_texture: texture_2d<f32>;      // outline
+@group(0) @binding(4) var texture_sampler: sampler;             // outline
+@group(0) @binding(5) var<uniform> settings: OutlineSettings;   // outline
+
+@fragment
+fn init(in: FullscreenVertexOutput) -> @location(0) vec2<u32> {
+    let pixel = vec2<u32>(in.position.xy);
+    if textureLoad(mask_texture, pixel, 0).r != 0u {
+        return pixel;
+    }
+    return vec2(NO_SEED);
+}
+
+@fragment
+fn jump(in: FullscreenVertexOutput) -> @location(0) vec2<u32> {
+    let size  = vec2<i32>(textureDimensions(seed_texture));
+    let pixel = vec2<i32>(in.position.xy);
+
+    var nearest_seed = vec2(NO_SEED);
+    var nearest_distance = 3.4e38;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let neighbor = pixel + vec2(x, y) * i32(jump_flood.step);
+            if any(neighbor < vec2(0)) || any(neighbor >= size) {
+                continue;
+            }
+            let seed = textureLoad(seed_texture, neighbor, 0).rg;
+            if seed.x == NO_SEED {
+                continue;
+            }
+            let offset = vec2<f32>(vec2<i32>(seed) - pixel);
+            let distance = dot(offset, offset);
+            if distance < nearest_distance {
+                nearest_distance = distance;
+                nearest_seed = seed;
+            }
+        }
+    }
+    return nearest_seed;
+}
+
+@fragment
+fn outline(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(screen_texture, texture_sampler, in.uv);
+    let pixel = vec2<u32>(in.position.xy);
+
+    // オブジェクトの内側には描かない
+    if textureLoad(mask_texture, pixel, 0).r != 0u {
+        return color;
+    }
+
+    let seed = textureLoad(seed_texture, pixel, 0).rg;
+    if seed.x == NO_SEED {
+        return color;
+    }
+    let id = textureLoad(mask_texture, seed, 0).r;
+    if id == 0u || id > settings.count {
+        return color;
+    }
+
+    let entry = settings.objects[id - 1u];
+    let distance = length(vec2<f32>(seed) - vec2<f32>(pixel));
+
+    // 太さの境界の前後 0.5 ピクセルで濃さを変えてアンチエイリアスする
+    var coverage = 1.0 - smoothstep(entry.width - 0.5, entry.width + 0.5, distance);
+    // 境界の外側は光の長さに向かってなめらかに減衰させる
+    if jump_flood.glow > 0.0 {
+        let glow = 1.0 - clamp((distance - entry.width) / jump_flood.glow, 0.0, 1.0);
+        coverage = max(coverage, glow * glow);
+    }
+
+    return vec4(mix(color.rgb, entry.color.rgb, entry.color.a * coverage), color.a);
+}
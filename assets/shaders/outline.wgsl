@@ -0,0 +1,70 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+//
+// オブジェクトごとのアウトライン
+// マスクの ID が 0 のピクセルから周囲を探し、オブジェクトごとの太さ以内に ID があればその色で塗る
+//
+
+struct OutlineEntry {
+    color:    vec4<f32> // 線形 RGB、a は合成の強さ
+    , width:  f32
+    , _pad_0: f32
+    , _pad_1: f32
+    , _pad_2: f32
+}
+
+// MAX_OUTLINED_OBJECTS と同じ長さにすること
+const MAX_OUTLINED_OBJECTS: u32 = 256u;
+// MAX_OUTLINE_WIDTH と同じ値にすること（探索範囲の上限）
+const MAX_OUTLINE_WIDTH: f32 = 16.0;
+
+struct OutlineSettings {
+    count:       u32
+    , max_width: f32 // 全オブジェクトの中で最も太いアウトラインの太さ（探索範囲）
+    , _pad_0:    u32
+    , _pad_1:    u32
+    , objects:   array<OutlineEntry, MAX_OUTLINED_OBJECTS> // ID - 1 番目がそのオブジェクトの設定
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var mask_texture: texture_2d<u32>;
+@group(0) @binding(3) var<uniform> settings: OutlineSettings;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(screen_texture, texture_sampler, in.uv);
+    let size  = vec2<i32>(textureDimensions(mask_texture));
+    let pixel = vec2<i32>(in.position.xy);
+
+    // オブジェクトの内側には描かない
+    if textureLoad(mask_texture, pixel, 0).r != 0u {
+        return color;
+    }
+
+    // 太さ以内で最も近いオブジェクトを探す
+    let max_width = min(settings.max_width, MAX_OUTLINE_WIDTH);
+    let radius = i32(ceil(max_width));
+    var nearest_distance = max_width + 1.0;
+    var nearest_id = 0u;
+    for (var y = -radius; y <= radius; y++) {
+        for (var x = -radius; x <= radius; x++) {
+            let neighbor = clamp(pixel + vec2(x, y), vec2(0), size - vec2(1));
+            let id = textureLoad(mask_texture, neighbor, 0).r;
+            if id == 0u || id > settings.count {
+                continue;
+            }
+            let distance = length(vec2<f32>(f32(x), f32(y)));
+            if distance < nearest_distance && distance <= settings.objects[id - 1u].width {
+                nearest_distance = distance;
+                nearest_id = id;
+            }
+        }
+    }
+
+    if nearest_id == 0u {
+        return color;
+    }
+    let outline = settings.objects[nearest_id - 1u].color;
+    return vec4(mix(color.rgb, outline.rgb, outline.a), color.a);
+}
@@ -0,0 +1,45 @@
+//
+// アウトラインのマスク
+// Outlined が付いたメッシュだけを描画し、ピクセルにオブジェクトの ID（1 始まり、0 は何もない）を書き込む
+// シーンの深度と比べて、他のオブジェクトに隠れている部分は書き込まない
+// ※ 頂点の位置だけを使うため、スキニングとモーフターゲットは反映されない（バインドポーズの形になる）
+//
+
+struct OutlineView {
+    clip_from_world: mat4x4<f32>
+}
+
+struct OutlineObject {
+    world_from_local: mat4x4<f32>
+    , id:     u32
+    , _pad_0: u32
+    , _pad_1: u32
+    , _pad_2: u32
+}
+
+@group(0) @binding(0) var<uniform> view: OutlineView;
+// メインパスの深度（MSAA のカメラではマルチサンプルのテクスチャの1サンプル目を使う）
+#ifdef MULTISAMPLED
+@group(0) @binding(1) var scene_depth: texture_depth_multisampled_2d;
+#else
+@group(0) @binding(1) var scene_depth: texture_depth_2d;
+#endif
+@group(1) @binding(0) var<uniform> object: OutlineObject;
+
+// メインパスと同じ面でも計算の誤差で深度がずれるので、この割合までは隠れていないとみなす
+const DEPTH_TOLERANCE: f32 = 1e-3;
+
+@vertex
+fn vertex(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+    return view.clip_from_world * object.world_from_local * vec4(position, 1.0);
+}
+
+@fragment
+fn fragment(@builtin(position) position: vec4<f32>) -> @location(0) u32 {
+    // reverse-Z なので大きい方が手前
+    let depth = textureLoad(scene_depth, vec2<i32>(position.xy), 0);
+    if position.z * (1.0 + DEPTH_TOLERANCE) < depth {
+        discard;
+    }
+    return object.id;
+}
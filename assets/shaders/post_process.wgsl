@@ -9,17 +9,40 @@ struct DitherSettings {
     , intensity:      f32
     , scale:          i32
     , weight_scaling: f32
-    , _pad_0:         f32
-    , _pad_1:         f32
-    , _pad_2:         f32
+    , mode:           u32
+    , diffusion_kernel: u32
+    , serpentine:     u32
+    , bayer_levels:   u32
+    , _pad_0:         u32
+    , _pad_1:         u32
+    , _pad_2:         u32
 }
 
 struct EdgeSettings {
     is_enable:       u32
     , edge_strength: f32
-#ifdef SIXTEEN_BYTE_ALIGNMENT
-    , _edge_padding: vec2<u32>
-#endif
+    , luminance_enable: u32
+    , depth_enable:  u32
+    , depth_threshold: f32
+    , normal_enable: u32
+    , normal_threshold: f32
+    , kernel:        u32 // シェーダー定義 EDGE_KERNEL_* で切り替えるため参照しない
+    , non_max_suppression: u32 // シェーダー定義 EDGE_NON_MAX_SUPPRESSION で切り替えるため参照しない
+    , thickness:     f32
+    , blend_mode:    u32
+    , color:         vec4<f32> // 線形 RGB、a は合成の強さ
+}
+
+struct HalftoneSettings {
+    is_enable:     u32
+    , is_cmyk:     u32
+    , dot_shape:   u32
+    , cell_size:   f32
+    , angle:       f32
+    , _pad_0:      f32
+    , _pad_1:      f32
+    , _pad_2:      f32
+    , cmyk_angles: vec4<f32>
 }
 
 struct PostProcessSettings {
@@ -29,67 +52,589 @@ struct PostProcessSettings {
     , _pad_0:        f32
     , dither: DitherSettings
     , edge: EdgeSettings
+    , halftone: HalftoneSettings
+    , projection: vec4<f32> // x: clip_from_view[2][2], y: clip_from_view[3][2], z: 透視投影なら 1.0
 #ifdef SIXTEEN_BYTE_ALIGNMENT
     , _webgl2_padding: vec3<f32>
 #endif
 }
 @group(0) @binding(2) var<uniform> settings: PostProcessSettings;
+@group(0) @binding(3) var blue_noise_texture: texture_2d<f32>;
+// 減色パレット（1行目: 線形 RGB、2行目: OKLab、幅が色数）
+@group(0) @binding(4) var palette_texture: texture_2d<f32>;
+// ベイヤー行列（64x64、0.0～1.0 に正規化済み）
+@group(0) @binding(5) var bayer_texture: texture_2d<f32>;
+// ベイヤー行列の代わりに使う閾値マップ（未指定の場合は 1x1 の代替画像）
+@group(0) @binding(6) var threshold_map_texture: texture_2d<f32>;
+// 深度プリパスの深度（プリパスがないビューでは 1x1 の代替テクスチャ）
+@group(0) @binding(7) var depth_texture: texture_depth_2d;
+// 法線プリパスのワールド空間の法線（0.0～1.0 に符号化済み、プリパスがないビューでは 1x1 の代替テクスチャ）
+@group(0) @binding(8) var normal_texture: texture_2d<f32>;
+
+// HalftoneSettings.dot_shape の値
+const HALFTONE_DOT_ROUND: u32   = 0u;
+const HALFTONE_DOT_ELLIPSE: u32 = 1u;
+const HALFTONE_DOT_LINE: u32    = 2u;
+
+// エッジの色の合成方法
+const EDGE_BLEND_REPLACE: u32  = 0u;
+const EDGE_BLEND_MULTIPLY: u32 = 1u;
+const EDGE_BLEND_ADDITIVE: u32 = 2u;
+const EDGE_BLEND_DARKEN: u32   = 3u;
+
+// DitherSettings.bayer_levels の値（ビット n が 2^n x 2^n の行列）
+const BAYER_MAX_ORDER: u32      = 6u;
+const BAYER_LEVELS_MASK: u32    = 0x7eu; // 2x2 ～ 64x64
+const BAYER_LEVELS_DEFAULT: u32 = 0x0eu; // 2x2, 4x4, 8x8
 
 //
-// === Bayer 2x2 ===
+// === Bayer ===
+// 生成済みの 64x64 のベイヤー行列テクスチャの左上 size x size を繰り返し参照する
+// （64x64 の行列の左上 n x n は n x n の行列を正規化したものと一致する）
 //
-fn bayer2x2(x: i32, y: i32) -> f32 {
-    let mat = array<i32, 4>(0, 2, 3, 1);
-    let tx = x % 2;
-    let ty = y % 2;
-    return f32(mat[ty * 2 + tx]) / 4.0;
+fn bayer(x: i32, y: i32, size: i32) -> f32 {
+    return textureLoad(bayer_texture, vec2<i32>(x % size, y % size), 0).r;
 }
 
 //
-// === Bayer 4x4 ===
+// bayer_levels で指定されたサイズのベイヤー行列を輝度に応じた重みでブレンドした閾値を返す
+// 重み（低輝度→粗い, 高輝度→細かい）
+// 小さい行列から順に 0.0, 1.5, 3.0 ... を中心とした三角形の重みで、両端は外側に向かって 1.0 のまま
+//
+fn bayer_threshold(x: i32, y: i32, normalized_gray: f32) -> f32 {
+    var levels = settings.dither.bayer_levels & BAYER_LEVELS_MASK;
+    if levels == 0u {
+        levels = BAYER_LEVELS_DEFAULT;
+    }
+    let count = i32(countOneBits(levels));
+    let position = normalized_gray * settings.dither.weight_scaling;
+
+    var weighted = 0.0;
+    var sum = 0.0;
+    var index = 0;
+    for (var order = 1u; order <= BAYER_MAX_ORDER; order++) {
+        if (levels & (1u << order)) == 0u {
+            continue;
+        }
+
+        let center = f32(index) * 1.5;
+        var weight: f32;
+        if count == 1 {
+            weight = 1.0;
+        } else if index == 0 {
+            weight = clamp(1.0 - position, 0.0, 1.0);
+        } else if index == count - 1 {
+            weight = clamp(position - (center - 1.0), 0.0, 1.0);
+        } else {
+            weight = clamp(1.0 - abs(position - center), 0.0, 1.0);
+        }
+
+        weighted += bayer(x, y, i32(1u << order)) * weight;
+        sum += weight;
+        index++;
+    }
+
+    return weighted / max(sum, 1e-5);
+}
+
+//
+// === Threshold map ===
+// 画像から読み込んだ閾値マップをタイル状に繰り返して閾値を取得する
+// 閾値マップを使うかどうかは THRESHOLD_MAP のシェーダー定義で切り替える（1x1 の閾値マップも使える）
+//
+fn threshold_map(x: i32, y: i32) -> f32 {
+    let size = vec2<i32>(textureDimensions(threshold_map_texture));
+    return textureLoad(threshold_map_texture, vec2<i32>(x % size.x, y % size.y), 0).r;
+}
+
 //
-fn bayer4x4(x: i32, y: i32) -> f32 {
-    let mat = array<i32, 16>(
-         0,  8,  2, 10,
-        12,  4, 14,  6,
-         3, 11,  1,  9,
-        15,  7, 13,  5
+// === Blue noise ===
+// タイル状に敷き詰めたブルーノイズテクスチャから閾値を取得する
+//
+fn blue_noise(x: i32, y: i32) -> f32 {
+    let size = vec2<i32>(textureDimensions(blue_noise_texture));
+    let tx = x % size.x;
+    let ty = y % size.y;
+    return textureLoad(blue_noise_texture, vec2<i32>(tx, ty), 0).r;
+}
+
+//
+// 線形 sRGB から OKLab へ変換する
+// 参考
+// https://bottosson.github.io/posts/oklab/
+//
+fn linear_srgb_to_oklab(c: vec3<f32>) -> vec3<f32> {
+    let l = 0.4122214708 * c.r + 0.5363325363 * c.g + 0.0514459929 * c.b;
+    let m = 0.2119034982 * c.r + 0.6806995451 * c.g + 0.1073969566 * c.b;
+    let s = 0.0883024619 * c.r + 0.2817188376 * c.g + 0.6299787005 * c.b;
+
+    let l_ = pow(max(l, 0.0), 1.0 / 3.0);
+    let m_ = pow(max(m, 0.0), 1.0 / 3.0);
+    let s_ = pow(max(s, 0.0), 1.0 / 3.0);
+
+    return vec3<f32>(
+        0.2104542553 * l_ + 0.7936177850 * m_ - 0.0040720468 * s_,
+        1.9779984951 * l_ - 2.4285922050 * m_ + 0.4505937099 * s_,
+        0.0259040371 * l_ + 0.7827717662 * m_ - 0.8086757660 * s_
     );
-    let tx = x % 4;
-    let ty = y % 4;
-    return f32(mat[ty * 4 + tx]) / 16.0;
 }
 
 //
-// === Bayer 8x8 ===
+// === Palette ===
+// OKLab 空間でパレット内の最も近い色と2番目に近い色を探し、
+// 2色を結ぶ線分上での位置と閾値を比べてどちらの色にするかを決める
+//
+fn palette_dither(color: vec3<f32>, threshold: f32) -> vec3<f32> {
+    let palette_size = i32(textureDimensions(palette_texture).x);
+    let lab = linear_srgb_to_oklab(color);
+
+    var nearest = 0;
+    var second  = 0;
+    var nearest_distance = 1e10;
+    var second_distance  = 1e10;
+    for (var i = 0; i < palette_size; i++) {
+        let diff = textureLoad(palette_texture, vec2<i32>(i, 1), 0).rgb - lab;
+        let distance = dot(diff, diff);
+        if distance < nearest_distance {
+            second = nearest;
+            second_distance = nearest_distance;
+            nearest = i;
+            nearest_distance = distance;
+        } else if distance < second_distance {
+            second = i;
+            second_distance = distance;
+        }
+    }
+
+    // 最も近い色から2番目に近い色へどれだけ寄っているか（0.0～1.0）
+    let nearest_lab = textureLoad(palette_texture, vec2<i32>(nearest, 1), 0).rgb;
+    let second_lab  = textureLoad(palette_texture, vec2<i32>(second, 1), 0).rgb;
+    let segment = second_lab - nearest_lab;
+    let ratio = clamp(dot(lab - nearest_lab, segment) / max(dot(segment, segment), 1e-8), 0.0, 1.0);
+
+    let index = select(nearest, second, ratio > threshold);
+    return textureLoad(palette_texture, vec2<i32>(index, 0), 0).rgb;
+}
+
+//
+// === Halftone ===
+// スクリーン角度で回転させたセルの中心からの距離と、インクの量から決めた網点の大きさを比べて
+// そのピクセルにインクが乗るかどうか（境界は1ピクセル分なめらかにする）を返す
 //
-fn bayer8x8(x: i32, y: i32) -> f32 {
-    let mat = array<i32, 64>(
-         0, 32,  8, 40,  2, 34, 10, 42,
-        48, 16, 56, 24, 50, 18, 58, 26,
-        12, 44,  4, 36, 14, 46,  6, 38,
-        60, 28, 52, 20, 62, 30, 54, 22,
-         3, 35, 11, 43,  1, 33,  9, 41,
-        51, 19, 59, 27, 49, 17, 57, 25,
-        15, 47,  7, 39, 13, 45,  5, 37,
-        63, 31, 55, 23, 61, 29, 53, 21
+fn halftone_ink(pixel: vec2<f32>, angle_degrees: f32, amount: f32) -> f32 {
+    let cell_size = max(settings.halftone.cell_size, 1.0);
+    let angle = radians(angle_degrees);
+    let rotation = mat2x2<f32>(cos(angle), -sin(angle), sin(angle), cos(angle));
+    let cell = fract(rotation * pixel / cell_size) - vec2(0.5);
+    let smoothing = 0.5 / cell_size;
+    let ink = clamp(amount, 0.0, 1.0);
+
+    var distance: f32;
+    var radius: f32;
+    switch settings.halftone.dot_shape {
+        case HALFTONE_DOT_ELLIPSE: {
+            // 縦長の楕円（インクが増えると隣の点と縦に繋がる）
+            distance = length(cell * vec2(1.0, 0.7));
+            radius = sqrt(ink) * 0.6;
+        }
+        case HALFTONE_DOT_LINE: {
+            // 線の太さをインクの量に比例させる
+            distance = abs(cell.y);
+            radius = ink * 0.5;
+        }
+        default: {
+            // 円（インクが最大の時にセルの角まで埋まる大きさ）
+            distance = length(cell);
+            radius = sqrt(ink) * 0.7072;
+        }
+    }
+
+    if ink <= 0.0 {
+        return 0.0;
+    }
+    return 1.0 - smoothstep(radius - smoothing, radius + smoothing, distance);
+}
+
+//
+// 単色または CMYK の網点で色を再現する
+//
+fn halftone(color: vec3<f32>, pixel: vec2<f32>) -> vec3<f32> {
+#ifndef HALFTONE_CMYK
+    let gray = dot(color, vec3(0.299, 0.587, 0.114));
+    return vec3(1.0 - halftone_ink(pixel, settings.halftone.angle, 1.0 - gray));
+#else
+    // RGB → CMYK
+    let k = 1.0 - max(color.r, max(color.g, color.b));
+    let cmy = select((vec3(1.0) - color - vec3(k)) / (1.0 - k), vec3(0.0), k >= 1.0);
+
+    let angles = settings.halftone.cmyk_angles;
+    let c_ink = halftone_ink(pixel, angles.x, cmy.x);
+    let m_ink = halftone_ink(pixel, angles.y, cmy.y);
+    let y_ink = halftone_ink(pixel, angles.z, cmy.z);
+    let k_ink = halftone_ink(pixel, angles.w, k);
+
+    // 白い紙にインクを重ねた色（減法混色）
+    return (vec3(1.0) - vec3(c_ink, m_ink, y_ink)) * (1.0 - k_ink);
+#endif
+}
+
+//
+// === HDR ===
+// HDR の値（1.0 を超える線形の色）は可逆なトーンマッピングで 0.0～1.0 に収めてから処理し、
+// 出力時に元の範囲に戻す（閾値や白黒判定を LDR と同じ値のまま使うため）
+// 戻す時は元のピクセルの明るさ（最低 1.0）を白の上限にする
+// ※ そのまま戻すとディザで 1.0 になったピクセルが 1.0e4 まで明るくなってしまう
+//
+fn hdr_compress(color: vec3<f32>) -> vec3<f32> {
+    return color / (1.0 + max(color.r, max(color.g, color.b)));
+}
+
+fn hdr_expand(color: vec3<f32>, white: f32) -> vec3<f32> {
+    let expanded = color / max(1.0 - max(color.r, max(color.g, color.b)), 1.0e-4);
+    // 色相を保ったまま白の上限に収める
+    return expanded * min(1.0, white / max(max(expanded.r, max(expanded.g, expanded.b)), 1.0e-4));
+}
+
+//
+// 処理した色を出力先の範囲に合わせて返す（original は処理前のピクセルの色）
+//
+fn output_color(color: vec3<f32>, alpha: f32, original: vec3<f32>) -> vec4<f32> {
+#ifdef HDR
+    return vec4(hdr_expand(color, max(1.0, max(original.r, max(original.g, original.b)))), alpha);
+#else
+    return vec4(color, alpha);
+#endif
+}
+
+//
+// === Edge kernel ===
+// 輝度の勾配をシェーダー定義で選んだカーネルで求める
+// どのカーネルも中心差分（右 - 左）と同じ程度の大きさになるよう重みの合計で割っているので、
+// カーネルを切り替えても edge_strength の閾値をそのまま使える
+//
+const LUMA_WEIGHTS: vec3<f32> = vec3(0.299, 0.587, 0.114);
+
+fn luminance(uv: vec2<f32>, offset: vec2<f32>, x: f32, y: f32) -> f32 {
+    // 関数内の分岐から呼ばれるので微分を使わない textureSampleLevel で読む
+    let color = textureSampleLevel(screen_texture, texture_sampler, uv + offset * vec2(x, y), 0.0).rgb;
+#ifdef HDR
+    return dot(hdr_compress(color), LUMA_WEIGHTS);
+#else
+    return dot(color, LUMA_WEIGHTS);
+#endif
+}
+
+//
+// 3x3 の分離可能なカーネル（微分方向 [-1, 0, 1]、平滑化方向 [side, center, side]）
+//
+fn separable_gradient(uv: vec2<f32>, offset: vec2<f32>, side: f32, center: f32) -> vec2<f32> {
+    let tl = luminance(uv, offset, -1.0, -1.0);
+    let t  = luminance(uv, offset,  0.0, -1.0);
+    let tr = luminance(uv, offset,  1.0, -1.0);
+    let l  = luminance(uv, offset, -1.0,  0.0);
+    let r  = luminance(uv, offset,  1.0,  0.0);
+    let bl = luminance(uv, offset, -1.0,  1.0);
+    let b  = luminance(uv, offset,  0.0,  1.0);
+    let br = luminance(uv, offset,  1.0,  1.0);
+    let dx = side * (tr - tl) + center * (r - l) + side * (br - bl);
+    let dy = side * (bl - tl) + center * (b - t) + side * (br - tr);
+    return vec2(dx, dy) / (2.0 * side + center);
+}
+
+//
+// 輝度の勾配（ラプラシアンは向きを持たないので x に絶対値を入れる）
+//
+fn luminance_gradient(uv: vec2<f32>, offset: vec2<f32>) -> vec2<f32> {
+#ifdef EDGE_KERNEL_SOBEL
+    return separable_gradient(uv, offset, 1.0, 2.0);
+#else ifdef EDGE_KERNEL_SCHARR
+    return separable_gradient(uv, offset, 3.0, 10.0);
+#else ifdef EDGE_KERNEL_PREWITT
+    return separable_gradient(uv, offset, 1.0, 1.0);
+#else ifdef EDGE_KERNEL_ROBERTS
+    // 斜め方向の差分（2x2 なので半ピクセルずれるが 1 ピクセル幅の線になる）
+    let c  = luminance(uv, offset, 0.0, 0.0);
+    let r  = luminance(uv, offset, 1.0, 0.0);
+    let b  = luminance(uv, offset, 0.0, 1.0);
+    let br = luminance(uv, offset, 1.0, 1.0);
+    return vec2(br - c, b - r);
+#else ifdef EDGE_KERNEL_LAPLACIAN
+    let c = luminance(uv, offset, 0.0, 0.0);
+    let laplacian = luminance(uv, offset, -1.0, 0.0) + luminance(uv, offset, 1.0, 0.0)
+        + luminance(uv, offset, 0.0, -1.0) + luminance(uv, offset, 0.0, 1.0) - 4.0 * c;
+    return vec2(abs(laplacian), 0.0);
+#else
+    // 中心差分
+    let dx = luminance(uv, offset, 1.0, 0.0) - luminance(uv, offset, -1.0, 0.0);
+    let dy = luminance(uv, offset, 0.0, 1.0) - luminance(uv, offset, 0.0, -1.0);
+    return vec2(dx, dy);
+#endif
+}
+
+//
+// 輝度の勾配の大きさが閾値を超えていればエッジとする
+// EDGE_NON_MAX_SUPPRESSION が定義されている場合は Canny 法と同じく、勾配の向きの前後のピクセルより
+// 勾配が大きい場合だけを残して線を1ピクセル幅にする
+//
+fn is_luminance_edge(uv: vec2<f32>, offset: vec2<f32>) -> bool {
+    let gradient = luminance_gradient(uv, offset);
+    let magnitude = length(gradient);
+    if magnitude <= settings.edge.edge_strength {
+        return false;
+    }
+#ifdef EDGE_NON_MAX_SUPPRESSION
+#ifdef EDGE_KERNEL_LAPLACIAN
+    // ラプラシアンは向きがないので上下左右のすべてと比べる
+    let neighbors = array(vec2(1.0, 0.0), vec2(0.0, 1.0));
+    for (var i = 0; i < 2; i++) {
+        let step = offset * neighbors[i];
+        if magnitude < length(luminance_gradient(uv + step, offset))
+            || magnitude < length(luminance_gradient(uv - step, offset)) {
+            return false;
+        }
+    }
+#else
+    // 勾配の向きを 0°/45°/90°/135° の4方向に丸めて前後のピクセルを決める
+    let angle = atan2(gradient.y, gradient.x);
+    let sector = round(angle / radians(45.0));
+    let direction = vec2(round(cos(sector * radians(45.0))), round(sin(sector * radians(45.0))));
+    let step = offset * direction;
+    if magnitude < length(luminance_gradient(uv + step, offset))
+        || magnitude <= length(luminance_gradient(uv - step, offset)) {
+        return false;
+    }
+#endif
+#endif
+    return true;
+}
+
+//
+// エッジのピクセルの色を合成方法に従って求める
+//
+fn edge_color(base: vec3<f32>) -> vec3<f32> {
+    let color = settings.edge.color;
+    switch settings.edge.blend_mode {
+        case EDGE_BLEND_MULTIPLY: {
+            return mix(base, base * color.rgb, color.a);
+        }
+        case EDGE_BLEND_ADDITIVE: {
+            return base + color.rgb * color.a;
+        }
+        case EDGE_BLEND_DARKEN: {
+            return base * (1.0 - color.a);
+        }
+        default: {
+            return mix(base, color.rgb, color.a);
+        }
+    }
+}
+
+//
+// === Depth ===
+// 深度プリパスの値（reverse-Z の NDC の深度）をカメラからの距離に変換する
+//
+const MAX_LINEAR_DEPTH: f32 = 1e4;
+
+fn linear_depth(uv: vec2<f32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(depth_texture));
+    let coord = clamp(vec2<i32>(uv * vec2<f32>(size)), vec2(0), size - vec2(1));
+    let depth = textureLoad(depth_texture, coord, 0);
+
+    var distance: f32;
+    if settings.projection.z == 1.0 {
+        // 透視投影（深度 0.0 は無限遠）
+        distance = settings.projection.y / max(depth + settings.projection.x, 1e-7);
+    } else {
+        // 平行投影
+        distance = (settings.projection.y - depth) / settings.projection.x;
+    }
+    return min(distance, MAX_LINEAR_DEPTH);
+}
+
+//
+// 上下左右の深度の差が中心の深度に対して閾値を超えていればエッジとする
+// （距離に比例して差も大きくなるので割合で判定する）
+//
+fn is_depth_edge(uv: vec2<f32>, offset: vec2<f32>) -> bool {
+    let center = linear_depth(uv);
+    let left   = linear_depth(uv - vec2(offset.x, 0.0));
+    let right  = linear_depth(uv + vec2(offset.x, 0.0));
+    let top    = linear_depth(uv - vec2(0.0, offset.y));
+    let bottom = linear_depth(uv + vec2(0.0, offset.y));
+    let difference = length(vec2(right - left, bottom - top));
+    return difference / max(center, 1e-4) > settings.edge.depth_threshold;
+}
+
+//
+// === Normal ===
+// 法線プリパスの値を -1.0～1.0 の法線に戻す
+//
+fn world_normal(uv: vec2<f32>) -> vec3<f32> {
+    let size = vec2<i32>(textureDimensions(normal_texture));
+    let coord = clamp(vec2<i32>(uv * vec2<f32>(size)), vec2(0), size - vec2(1));
+    return normalize(textureLoad(normal_texture, coord, 0).xyz * 2.0 - 1.0);
+}
+
+//
+// 上下左右の法線と中心の法線のなす角度が閾値を超えていればエッジとする（立方体の辺などの折れ目）
+//
+fn is_normal_edge(uv: vec2<f32>, offset: vec2<f32>) -> bool {
+    let center = world_normal(uv);
+    let left   = world_normal(uv - vec2(offset.x, 0.0));
+    let right  = world_normal(uv + vec2(offset.x, 0.0));
+    let top    = world_normal(uv - vec2(0.0, offset.y));
+    let bottom = world_normal(uv + vec2(0.0, offset.y));
+    let min_cos = min(
+        min(dot(center, left), dot(center, right))
+        , min(dot(center, top), dot(center, bottom))
     );
-    let tx = x % 8;
-    let ty = y % 8;
-    return f32(mat[ty * 8 + tx]) / 64.0;
+    return min_cos < cos(radians(settings.edge.normal_threshold));
+}
+
+//
+// === Edge thickness ===
+// 1ピクセル幅のエッジを半径 (thickness - 1) / 2 の円の範囲で膨張させ、線の太さを thickness ピクセルにする
+// 探索範囲は MAX_EDGE_RADIUS ピクセルまで
+//
+const MAX_EDGE_RADIUS: i32 = 8;
+
+fn is_edge_at(uv: vec2<f32>, offset: vec2<f32>) -> bool {
+    var is_edge = false;
+#ifdef EDGE_LUMINANCE
+    is_edge = is_edge || is_luminance_edge(uv, offset);
+#endif
+#ifdef EDGE_DEPTH
+    is_edge = is_edge || is_depth_edge(uv, offset);
+#endif
+#ifdef EDGE_NORMAL
+    is_edge = is_edge || is_normal_edge(uv, offset);
+#endif
+    return is_edge;
+}
+
+fn is_thick_edge(uv: vec2<f32>, screen_size: vec2<f32>) -> bool {
+    let offset = 1.0 / screen_size;
+    let radius = max(settings.edge.thickness - 1.0, 0.0) * 0.5;
+    let reach = min(i32(ceil(radius)), MAX_EDGE_RADIUS);
+    for (var dy = -reach; dy <= reach; dy++) {
+        for (var dx = -reach; dx <= reach; dx++) {
+            let distance = vec2(f32(dx), f32(dy));
+            if dot(distance, distance) <= radius * radius + 1e-4 && is_edge_at(uv + distance * offset, offset) {
+                return true;
+            }
+        }
+    }
+    return false;
+}
+
+//
+// === Dither ===
+// ディザの有無や種類はシェーダー定義で切り替える
+//   DITHER                 ディザを適用する（定義がない場合はディザをかけずに出力する）
+//   DITHER_BLUE_NOISE      ブルーノイズの閾値を使う（定義がない場合はベイヤー行列か閾値マップ）
+//   THRESHOLD_MAP          ベイヤー行列の代わりに閾値マップを使う
+//   DITHER_ERROR_DIFFUSION 誤差拡散（後段のパスで量子化するのでここではディザをかけない）
+//
+fn dither_threshold(coord: vec2<i32>, normalized_gray: f32) -> f32 {
+#ifdef DITHER_BLUE_NOISE
+    // ブルーノイズは濃淡によらず同じテクスチャから閾値を取る
+    return blue_noise(coord.x, coord.y);
+#else
+#ifdef THRESHOLD_MAP
+    // 閾値マップが指定されている場合はベイヤー行列の代わりに使う
+    return threshold_map(coord.x, coord.y);
+#else
+    // 正規化されたグレーの色数値からどのベイヤー行列を適用するかを決めて閾値を算出
+    return bayer_threshold(coord.x, coord.y, normalized_gray);
+#endif
+#endif
+}
+
+//
+// モノクロディザの白黒を決める
+//
+fn monochrome_dither(gray: f32, coord: vec2<i32>, normalized_gray: f32) -> f32 {
+#ifdef DITHER
+    if gray < settings.dither.intensity {
+        // ピクセルの数値が閾値より小さい場合は常に黒色にする
+        return 0.0;
+    }
+#ifdef DITHER_ERROR_DIFFUSION
+    // 誤差拡散は後段のパスで2値化するのでグレーのまま渡す
+    return gray;
+#else
+    // 閾値を超えたピクセルは白色で返す
+    return select(0.0, 1.0, gray > dither_threshold(coord, normalized_gray));
+#endif
+#else
+    return gray;
+#endif
+}
+
+//
+// パレット内の色でディザをかける
+//
+fn palette_color(base_color: vec3<f32>, gray: f32, coord: vec2<i32>, normalized_gray: f32) -> vec3<f32> {
+#ifdef DITHER
+#ifdef DITHER_ERROR_DIFFUSION
+    // 誤差拡散は後段のパスでパレット内の色に量子化するので元色のまま渡す
+    return base_color;
+#else
+    if gray >= settings.dither.intensity {
+        return palette_dither(base_color, dither_threshold(coord, normalized_gray));
+    }
+    // 低輝度はディザをかけずに最も近い色にする
+    return palette_dither(base_color, 1.0);
+#endif
+#else
+    // ディザなしの場合はすべて最も近い色にする
+    return palette_dither(base_color, 1.0);
+#endif
+}
+
+//
+// 元色と黒または白の間でディザをかける
+//
+fn color_dither(base_color: vec3<f32>, gray: f32, coord: vec2<i32>, normalized_gray: f32) -> vec3<f32> {
+#ifdef DITHER
+#ifndef DITHER_ERROR_DIFFUSION
+    if gray >= settings.dither.intensity {
+        let threshold = dither_threshold(coord, normalized_gray);
+
+        // 元色が黒寄りか白寄りかで、相手を黒/白に自動選択
+        let endpoint_is_white = normalized_gray >= 0.5;
+        let endpoint = select(vec3(0.0), vec3(1.0), endpoint_is_white);
+
+        // Ordered dither:
+        // - 白を相手にする時は g > threshold で相手色（白）を増やす
+        // - 黒を相手にする時は g < threshold で相手色（黒）を増やす
+        let use_endpoint =
+            (endpoint_is_white && (normalized_gray > threshold)) ||
+            (!endpoint_is_white && (normalized_gray < threshold));
+
+        return select(base_color, endpoint, use_endpoint);
+    }
+#endif
+#endif
+    // 低輝度はそのまま原色で出力
+    // 誤差拡散は後段のパスで量子化するのでここでは何もしない
+    return base_color;
 }
 
 @fragment
 fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+#ifndef POST_PROCESS_ENABLE
     // 無効時は何もせず元色を返す
-    if settings.is_enable == 0u {
-        return textureSample(screen_texture, texture_sampler, in.uv);
-    }
-
+    return textureSample(screen_texture, texture_sampler, in.uv);
+#else
     // スクリーンに描画されているテクスチャ（描画イメージ）取得
-    let tex_color  = textureSample(screen_texture, texture_sampler, in.uv);
+    let screen_color = textureSample(screen_texture, texture_sampler, in.uv);
+#ifdef HDR
+    let tex_color  = vec4(hdr_compress(screen_color.rgb), screen_color.a);
+#else
+    let tex_color  = screen_color;
+#endif
     let base_color = tex_color.rgb;          // ピクセルのオリジナル色
-    let comp_color = vec3(1.0) - base_color; // ピクセルのオリジナル色の補色（例: 白↔黒, マゼンタ↔緑）
 
     // テクスチャをグレースケールに変換
     // ITU-R Rec BT.601
@@ -107,78 +652,58 @@ fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
         i32(in.uv.y * screen_size.y) / scale
     );
 
-    // ディザの行列
-    let t2 = bayer2x2(coord.x, coord.y);
-    let t4 = bayer4x4(coord.x, coord.y);
-    let t8 = bayer8x8(coord.x, coord.y);
-
     // グレーの色を0.0～1.0に正規化
     let normalized_gray = clamp((gray - 0.1) / 0.9, 0.0, 1.0);
 
-    //
-    // 重み（低輝度→粗い, 高輝度→細かい）
-    // 
-    // 正規化されたグレーの色数値からどのベイヤー行列を適用するかを決める
-    //
-    let w2 = clamp(1.0 - normalized_gray * settings.dither.weight_scaling, 0.0, 1.0);
-    let w4 = clamp(1.0 - abs(normalized_gray * settings.dither.weight_scaling - 1.5), 0.0, 1.0);
-    let w8 = clamp(normalized_gray * settings.dither.weight_scaling - 2.0, 0.0, 1.0);
-
-    // 重みからかけるディザ行列の判断材料となる閾値を算出
-    let sum = max(w2 + w4 + w8, 1e-5);
-    let threshold = (t2 * w2 + t4 * w4 + t8 * w8) / sum; // ベイヤー行列をかけて重みを計算している
-
     // エッジ検出 (ピクセルの色値から明暗の差を算出している)
-    let offset = vec2<f32>(1.0 / screen_size.x, 1.0 / screen_size.y);
-    let left   = dot(textureSample(screen_texture, texture_sampler, in.uv - vec2(offset.x, 0.0)).rgb, vec3(0.299, 0.587, 0.114));
-    let right  = dot(textureSample(screen_texture, texture_sampler, in.uv + vec2(offset.x, 0.0)).rgb, vec3(0.299, 0.587, 0.114));
-    let top    = dot(textureSample(screen_texture, texture_sampler, in.uv - vec2(0.0, offset.y)).rgb, vec3(0.299, 0.587, 0.114));
-    let bottom = dot(textureSample(screen_texture, texture_sampler, in.uv + vec2(0.0, offset.y)).rgb, vec3(0.299, 0.587, 0.114));
-    let dx = right - left;
-    let dy = bottom - top;
-    let edge_strength = length(vec2(dx, dy));
-    let is_edge = edge_strength > settings.edge.edge_strength;
-
-    if settings.dither.is_monochrome == 1u {
-        var black_or_white: f32;
-        if is_edge {
-            // エッジと検出されたピクセルは常に白色にする
-            black_or_white = 1.0;
-        } else if gray < settings.dither.intensity {
-            // ピクセルの数値が閾値より小さい場合は常に黒色にする
-            black_or_white = 0.0;
-        } else if gray > threshold {
-            // 閾値を超えたピクセルは白色で返す
-            black_or_white = 1.0;
-        } else {
-            black_or_white = 0.0;
-        }
+    // 1ピクセル幅で検出したエッジを太さに合わせて膨張させる
+    var is_edge = false;
+#ifdef EDGE
+    is_edge = is_thick_edge(in.uv, screen_size);
+#endif
 
-        return vec4(vec3(black_or_white), 1.0);
+#ifdef POST_PROCESS_EDGES_ONLY
+    // PostEffectStack の Edges の段階ではエッジだけを描く（ディザはスタックの Dither の段階か後のパスで行う）
+    var edge_rgb = base_color;
+    if is_edge {
+        edge_rgb = edge_color(base_color);
+    }
+    return output_color(edge_rgb, tex_color.a, screen_color.rgb);
+#else ifdef HALFTONE
+    // ハーフトーンはディザの代わりに適用する
+    let halftone_color = halftone(base_color, in.uv * screen_size);
+    if is_edge {
+        return output_color(edge_color(halftone_color), tex_color.a, screen_color.rgb);
+    }
+    return output_color(halftone_color, tex_color.a, screen_color.rgb);
+#else ifdef DITHER_MONOCHROME
+    var black_or_white: f32;
+    if is_edge {
+        // エッジと検出されたピクセルはエッジの色の明るさにする
+        black_or_white = dot(edge_color(vec3(gray)), LUMA_WEIGHTS);
     } else {
-        var out_rgb: vec3<f32>;
-        if near_black || near_white {
-            // 白と黒はディザなし
-            out_rgb = base_color;
-        } else if is_edge {
-            out_rgb = vec3<f32>(1.0, 1.0, 1.0);
-        } else if gray < settings.dither.intensity {
-            // 低輝度はそのまま原色で出力
-            out_rgb = base_color;
+        black_or_white = monochrome_dither(gray, coord, normalized_gray);
+    }
+    return output_color(vec3(black_or_white), 1.0, screen_color.rgb);
+#else
+    var out_rgb: vec3<f32>;
+    if textureDimensions(palette_texture).x >= 2u {
+        // パレット指定時はすべてのピクセルをパレット内の色にする
+        if is_edge {
+            // エッジの色もパレット内の最も近い色にする
+            out_rgb = palette_dither(edge_color(base_color), 1.0);
         } else {
-            // 元色が黒寄りか白寄りかで、相手を黒/白に自動選択
-            let endpoint_is_white = normalized_gray >= 0.5;
-            let endpoint = select(vec3(0.0), vec3(1.0), endpoint_is_white);
-
-            // Ordered dither:
-            // - 白を相手にする時は g > threshold で相手色（白）を増やす
-            // - 黒を相手にする時は g < threshold で相手色（黒）を増やす
-            let use_endpoint =
-                (endpoint_is_white && (normalized_gray > threshold)) ||
-                (!endpoint_is_white && (normalized_gray < threshold));
-
-            out_rgb = select(base_color, endpoint, use_endpoint);
+            out_rgb = palette_color(base_color, gray, coord, normalized_gray);
         }
-        return vec4(out_rgb, tex_color.a);
+    } else if near_black || near_white {
+        // 白と黒はディザなし
+        out_rgb = base_color;
+    } else if is_edge {
+        out_rgb = edge_color(base_color);
+    } else {
+        out_rgb = color_dither(base_color, gray, coord, normalized_gray);
     }
+    return output_color(out_rgb, tex_color.a, screen_color.rgb);
+#endif
+#endif
 }
@@ -0,0 +1,11 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+// post_process.wgsl が読み込めない・コンパイルできない場合に代わりに使うシェーダー
+// ポストプロセスと同じバインドグループのうち入力の画面とサンプラーだけを使い、そのまま出力する
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(screen_texture, texture_sampler, in.uv);
+}
@@ -10,11 +10,78 @@ pub const ASSETS_FONT_PATH: &str = "fonts/MoralerspaceXenon/MoralerspaceXenon-Re
 
 // シェーダーポストプロセス
 pub const DEFAULT_SHADER_PATH: &str       = "shaders/post_process.wgsl";
-pub const DEFAULT_POSTPROCESS_ENABLE: u32 = 1;    // ポストプロセスを適用するかどうか 1=ON 0=OFF
-pub const DEFAULT_DITHER_ENABLE: u32      = 1;    // ディザを適用するかどうか 1=ON 0=OFF
-pub const DEFAULT_DITHER_MONOCHROME: u32  = 0;    // モノクロディザにするかどうか 1=ON 0=OFF
+pub const ERROR_DIFFUSION_SHADER_PATH: &str = "shaders/error_diffusion.wgsl";
+pub const OUTLINE_MASK_SHADER_PATH: &str  = "shaders/outline_mask.wgsl";
+pub const OUTLINE_SHADER_PATH: &str       = "shaders/outline.wgsl";
+pub const JUMP_FLOOD_SHADER_PATH: &str    = "shaders/jump_flood.wgsl";
+pub const GRADING_SHADER_PATH: &str       = "shaders/grading.wgsl";
+pub const DEFAULT_POSTPROCESS_ENABLE: bool = true; // ポストプロセスを適用するかどうか
+pub const DEFAULT_DITHER_ENABLE: bool      = true; // ディザを適用するかどうか
+pub const DEFAULT_DITHER_MONOCHROME: bool  = false; // モノクロディザにするかどうか
+pub const DEFAULT_DIFFUSION_SERPENTINE: bool = false; // 誤差拡散を蛇行走査（1行ごとに左右反転）にするかどうか
+pub const ERROR_DIFFUSION_WORKGROUP_ROWS: u32 = 256;  // 誤差拡散の1回のディスパッチで処理する最大の行数（error_diffusion.wgsl のワークグループのサイズと合わせること）
+pub const ERROR_DIFFUSION_ROW_LAG: u32 = 5;          // 誤差拡散で走査方向が同じ下の行が遅れるピクセル数（error_diffusion.wgsl の ROW_LAG と合わせること）
+pub const ERROR_DIFFUSION_MAX_STEPS: u32 = 16384;    // 誤差拡散の1回のディスパッチのステップ数の上限（GPU のタイムアウトを避けるため）
 pub const DEFAULT_DITHER_INTENSITY: f32   = 0.01; // ディザをかけるグレースケールの色式値
 pub const DEFAULT_DITHER_SCALE: i32       = 2;    // ディザのスケール
+pub const DEFAULT_BAYER_LEVELS: u32       = BAYER_2X2 | BAYER_4X4 | BAYER_8X8; // ブレンドするベイヤー行列のサイズの組み合わせ
 pub const DEFAULT_WEIGHT_SCALE: f32       = 2.0;  // 閾値ごとにかけるディザ（ベイヤー行列）を決めるための係数、数値が大きいほどグレーの濃淡の識別が増えるがベイヤー行列の種類数に合わせないと意味がないので注意
-pub const DEFAULT_EDGE_ENABLE: u32        = 1;    // エッジを適用するかどうか 1=ON 0=OFF
+pub const DEFAULT_EDGE_ENABLE: bool        = true; // エッジを適用するかどうか
 pub const DEFAULT_EDGE_STRENGTH: f32      = 0.05; // エッジ強度の検出式値
+pub const DEFAULT_EDGE_LUMINANCE: bool     = true; // 輝度の差でエッジを検出するかどうか
+pub const DEFAULT_EDGE_NON_MAX_SUPPRESSION: bool = false; // 非極大値抑制でエッジを1ピクセル幅に細線化するかどうか
+pub const DEFAULT_EDGE_THICKNESS: f32     = 1.0;  // エッジの太さ（ピクセル、検出したエッジをこの太さまで膨張させる）
+pub const MAX_EDGE_THICKNESS: f32         = 17.0; // エッジの最大の太さ（ピクセル、post_process.wgsl の MAX_EDGE_RADIUS * 2 + 1 と合わせること）
+pub const DEFAULT_EDGE_COLOR: Vec4        = Vec4::new(1.0, 1.0, 1.0, 1.0); // エッジの色（線形 RGB、a は合成の強さ）
+pub const DEFAULT_OUTLINE_GLOW: f32       = 0.0;  // アウトラインの外側に広がる光の長さ（ピクセル、ジャンプフラッディングの場合のみ）
+pub const DEFAULT_HALFTONE_ENABLE: bool    = false; // ハーフトーンを適用するかどうか（ON の場合はディザの代わりに適用する）
+pub const DEFAULT_HALFTONE_CMYK: bool      = false; // CMYK の4版で網点を作るかどうか（OFF の場合は単色の1版）
+pub const DEFAULT_HALFTONE_CELL_SIZE: f32 = 8.0;  // 網点1つ分のセルの大きさ（ピクセル）
+pub const DEFAULT_HALFTONE_ANGLE: f32     = 45.0; // 単色の場合のスクリーン角度（度）
+pub const DEFAULT_HALFTONE_CMYK_ANGLES: Vec4 = Vec4::new(15.0, 75.0, 0.0, 45.0); // CMYK それぞれのスクリーン角度（度）
+pub const DEFAULT_GRADING_EXPOSURE: f32   = 0.0;  // 色調補正の露出（段）
+pub const DEFAULT_GRADING_CONTRAST: f32   = 1.0;  // 色調補正のコントラスト
+pub const DEFAULT_GRADING_SATURATION: f32 = 1.0;  // 色調補正の彩度
+pub const DEFAULT_BLUE_NOISE_SIZE: u32    = 64;   // ブルーノイズテクスチャの一辺のサイズ（64/128/256 など、大きいほど生成に時間がかかる）
+pub const DEFAULT_BLUE_NOISE_SEED: u64    = 1;    // ブルーノイズ生成に使う乱数のシード値
+pub const MAX_PALETTE_COLORS: usize       = 256;  // パレットに指定できる色の最大数
+pub const MAX_OUTLINED_OBJECTS: usize     = 256;  // アウトラインを描画できるオブジェクトの最大数（outline.wgsl の配列の長さと合わせること）
+pub const MAX_OUTLINE_WIDTH: f32          = 16.0; // 周囲を探索する描き方でのアウトラインの最大の太さ（ピクセル、outline.wgsl と合わせること）
+
+// ディザの閾値マップの種類
+pub const DITHER_MODE_BAYER: u32           = 0; // ベイヤー行列
+pub const DITHER_MODE_BLUE_NOISE: u32      = 1; // ブルーノイズテクスチャ
+pub const DITHER_MODE_ERROR_DIFFUSION: u32 = 2; // 誤差拡散（コンピュートシェーダーの別パスで処理する）
+
+// ハーフトーンの網点の形
+pub const HALFTONE_DOT_ROUND: u32   = 0; // 円
+pub const HALFTONE_DOT_ELLIPSE: u32 = 1; // 楕円
+pub const HALFTONE_DOT_LINE: u32    = 2; // 線
+
+// ベイヤー行列のサイズ（BayerLevels::new に OR で組み合わせて指定する、ビット n が 2^n x 2^n の行列）
+pub const BAYER_MAX_ORDER: u32 = 6; // 生成する最大の行列 2^6 = 64x64
+pub const BAYER_2X2: u32   = 1 << 1;
+pub const BAYER_4X4: u32   = 1 << 2;
+pub const BAYER_8X8: u32   = 1 << 3;
+pub const BAYER_16X16: u32 = 1 << 4;
+pub const BAYER_32X32: u32 = 1 << 5;
+pub const BAYER_64X64: u32 = 1 << 6;
+
+// 誤差拡散ディザの拡散カーネルの種類
+pub const DIFFUSION_KERNEL_FLOYD_STEINBERG: u32     = 0; // Floyd–Steinberg
+pub const DIFFUSION_KERNEL_ATKINSON: u32            = 1; // Atkinson（誤差の 3/4 だけを拡散する）
+pub const DIFFUSION_KERNEL_JARVIS_JUDICE_NINKE: u32 = 2; // Jarvis–Judice–Ninke
+
+// 輝度エッジの検出カーネルの種類（シェーダー定義で切り替えるため、使わないカーネルはコンパイルされない）
+pub const EDGE_KERNEL_CENTRAL_DIFFERENCE: u32 = 0; // 上下左右の中心差分
+pub const EDGE_KERNEL_SOBEL: u32              = 1; // Sobel 3x3
+pub const EDGE_KERNEL_SCHARR: u32             = 2; // Scharr 3x3（回転に対する誤差が小さい）
+pub const EDGE_KERNEL_PREWITT: u32            = 3; // Prewitt 3x3
+pub const EDGE_KERNEL_ROBERTS: u32            = 4; // Roberts cross 2x2
+pub const EDGE_KERNEL_LAPLACIAN: u32          = 5; // 4近傍のラプラシアン（勾配の向きを持たない）
+
+// エッジの色の合成方法（いずれも Edges::color の a の割合で元の色と混ぜる）
+pub const EDGE_BLEND_REPLACE: u32  = 0; // エッジの色で置き換える
+pub const EDGE_BLEND_MULTIPLY: u32 = 1; // 元の色にエッジの色を乗算する（インク風の線）
+pub const EDGE_BLEND_ADDITIVE: u32 = 2; // 元の色にエッジの色を加算する（光る線）
+pub const EDGE_BLEND_DARKEN: u32   = 3; // エッジの色は使わず元の色を暗くする
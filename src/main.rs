@@ -1,6 +1,3 @@
-pub mod consts;
-pub mod plugins;
-
 // --- Bevy 基本 ---
 use bevy::{
     prelude::*
@@ -16,9 +13,10 @@ use bevy::{
     , window::PrimaryWindow
 };
 
-use crate::consts::app::*;
-use crate::plugins::structs::components::PostProcessSettings;
-use crate::plugins::post_process::PostProcessPlugin;
+use bevy_post_process_sample::consts::app::*;
+use bevy_post_process_sample::plugins::structs::components::PostProcessSettings;
+use bevy_post_process_sample::plugins::post_process::PostProcessPlugin;
+use bevy_post_process_sample::plugins::structs::status::PostProcessStatus;
 
 #[derive(Component)]
 struct WindowCamera;
@@ -32,7 +30,7 @@ fn setup_window_camera(
 
     if let Some(window) = windows.iter_mut().next() {
         // ウィンドウを取得できた場合はディスプレイの解像度を最大画面サイズとする
-        display_full_size = UVec2::new( window.resolution.physical_width() as u32, window.resolution.physical_height() as u32)
+        display_full_size = UVec2::new( window.resolution.physical_width(), window.resolution.physical_height())
     }
 
     let bundle = (
@@ -75,7 +73,7 @@ struct Cube;
 fn setup_load_gltf(
     mut commands: Commands
     , asset_server: Res<AssetServer>
-    , mut unlit_gltfs: ResMut<UnlitGltfs>,
+    , _unlit_gltfs: ResMut<UnlitGltfs>, // 下のコメントアウトを戻す時は mut unlit_gltfs にする
 ) {
     let bundle = (
         SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset("glbs/cube_001.gltf")))
@@ -142,10 +140,16 @@ fn setup_default_light(
 #[derive(Component)]
 struct UiRoot;
 
+//
+// ポストプロセスの状態を表示するテキストのノード
+//
+#[derive(Component)]
+struct PostProcessStatusText;
+
 //
 // UIの親ノードをセットする
 //
-fn setup_ui_root(mut commands: Commands) {
+fn setup_ui_root(mut commands: Commands, asset_server: Res<AssetServer>) {
     // UIの起点となる親ノードのバンドル
     let bundle = (
             Node {
@@ -157,9 +161,43 @@ fn setup_ui_root(mut commands: Commands) {
             , UiRoot
     );
 
-    commands.spawn(bundle);
+    // ポストプロセスの状態のテキスト（失敗した時だけ表示する）
+    let status_text = (
+        Text::default()
+        , TextFont { font: asset_server.load(ASSETS_FONT_PATH), font_size: 14.0, ..default() }
+        , TextColor(Color::srgb(1.0, 0.3, 0.3))
+        , Visibility::Hidden
+        , PostProcessStatusText
+    );
+
+    commands.spawn(bundle).with_child(status_text);
+}
+
+//
+// ポストプロセスのシェーダーが使えない場合はエラーの内容を画面に表示する
+// 使えるようになったら（シェーダーを修正してホットリロードした場合など）表示を消す
+//
+fn show_post_process_status(
+    status: Res<PostProcessStatus>
+    , mut status_texts: Query<(&mut Text, &mut Visibility), With<PostProcessStatusText>>
+) {
+    if !status.is_changed() {
+        return;
+    }
+    let Ok((mut text, mut visibility)) = status_texts.single_mut() else {
+        return;
+    };
+
+    if let PostProcessStatus::Failed(error) = status.as_ref() {
+        text.0 = format!("post process disabled: {error}");
+        *visibility = Visibility::Inherited;
+    } else {
+        text.0.clear();
+        *visibility = Visibility::Hidden;
+    }
 }
 
+#[allow(clippy::type_complexity)]
 fn camera_rotation(
     input: Res<ButtonInput<MouseButton>>
     , mut transforms: ParamSet<(
@@ -239,7 +277,7 @@ fn main() {
                                           }
                                         )
                                         .set(ImagePlugin::default_nearest()) // 画像はすべて最近傍で補完する
-                                        , PostProcessPlugin
+                                        , PostProcessPlugin::default()
                     ));
 
     app.add_systems(Startup, (
@@ -251,5 +289,6 @@ fn main() {
        .add_systems(Update, (
             camera_rotation
             , materials_unlit
+            , show_post_process_status
         )).run();
 }
@@ -1,3 +1,5 @@
 pub mod structs;
 pub mod post_process;
 pub mod functions;
+pub mod post_effect;
+pub mod custom_post_process;
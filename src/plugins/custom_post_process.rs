@@ -0,0 +1,145 @@
+use std::{any::type_name, borrow::Cow, marker::PhantomData};
+use bevy::{
+    prelude::*
+    , core_pipeline::{
+        core_2d::graph::Core2d
+        , core_3d::graph::Core3d
+    }
+    , render::{
+        extract_component::{
+            ExtractComponent
+            , ExtractComponentPlugin
+            , UniformComponentPlugin
+        }
+        , extract_resource::ExtractResourcePlugin
+        , render_graph::{
+            InternedRenderLabel
+            , RenderGraph
+            , RenderGraphApp
+            , RenderGraphError
+            , RenderLabel
+            , RenderSubGraph
+            , ViewNodeRunner
+        }
+        , RenderApp
+        , RenderSet
+    }
+};
+use crate::plugins::structs::custom_post_process::*;
+use crate::plugins::structs::placement::PostProcessPlacement;
+use crate::plugins::functions::shader::rebuild_custom_pipeline_when_shader_changes;
+use crate::plugins::functions::graph::add_placement_edges;
+
+//
+// 設定のコンポーネント T とシェーダーを指定して独自のポストプロセスを追加するプラグイン
+// T の値はそのまま @group(0) @binding(2) のユニフォームとしてシェーダーに渡される
+// 型ごとに別のラベルでノードを登録するので、複数のインスタンスを同時にグラフに置ける
+// ノードは 3D と 2D の両方のグラフに登録され、placement の位置に置かれる
+//
+// app.add_plugins(
+//     CustomPostProcessPlugin::<VignetteSettings>::new("shaders/vignette.wgsl")
+//         .with_placement(PostProcessPlacement::AfterAntiAliasing)
+//         .after(PostProcessLabel)
+// );
+//
+pub struct CustomPostProcessPlugin<T> {
+    pub shader_path: Cow<'static, str>
+    , pub label: InternedRenderLabel
+    , pub placement: PostProcessPlacement // レンダーグラフ上のノードの位置
+    , pub after: Option<InternedRenderLabel>
+    , marker: PhantomData<fn() -> T>
+}
+
+impl<T: 'static> CustomPostProcessPlugin<T> {
+    pub fn new(shader_path: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            shader_path: shader_path.into()
+            , label: CustomPostProcessLabel(type_name::<T>()).intern()
+            , placement: PostProcessPlacement::default()
+            , after: None
+            , marker: PhantomData
+        }
+    }
+
+    // ノードのラベルを変更する（他のノードから順番を指定するため）
+    pub fn with_label(mut self, label: impl RenderLabel) -> Self {
+        self.label = label.intern();
+        self
+    }
+
+    // レンダーグラフ上のノードの位置を変更する
+    pub fn with_placement(mut self, placement: PostProcessPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    // placement の位置の中で、さらに指定したノードの後に描画する（そのグラフにノードがない場合は無視する）
+    pub fn after(mut self, label: impl RenderLabel) -> Self {
+        self.after = Some(label.intern());
+        self
+    }
+}
+
+impl<T> Plugin for CustomPostProcessPlugin<T>
+where
+    T: CustomPostProcessSettings + ExtractComponent<Out = T>
+{
+    fn build(&self, app: &mut App) {
+        let handle = app.world().resource::<AssetServer>().load(self.shader_path.to_string());
+        let shader = CustomPostProcessShader::<T>::new(handle);
+        app.insert_resource(shader.clone());
+        app.add_plugins((
+            ExtractComponentPlugin::<T>::default()
+            , UniformComponentPlugin::<T>::default()
+            , ExtractResourcePlugin::<CustomPostProcessShader<T>>::default()
+        ));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.insert_resource(shader);
+        render_app
+            .add_render_graph_node::<ViewNodeRunner<CustomPostProcessNode<T>>>(Core3d, self.label)
+            .add_render_graph_node::<ViewNodeRunner<CustomPostProcessNode<T>>>(Core2d, self.label)
+            .add_systems(
+                bevy::render::Render
+                , rebuild_custom_pipeline_when_shader_changes::<T>.in_set(RenderSet::Prepare)
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<CustomPostProcessPipeline<T>>();
+
+        let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
+        add_placement_edges(&mut render_graph, Core3d, &[self.label], self.placement, PostProcessPlacement::anchors_3d);
+        add_placement_edges(&mut render_graph, Core2d, &[self.label], self.placement, PostProcessPlacement::anchors_2d);
+        if let Some(after) = self.after {
+            add_after_edge(&mut render_graph, Core3d, after, self.label);
+            add_after_edge(&mut render_graph, Core2d, after, self.label);
+        }
+    }
+}
+
+//
+// after のノードがそのグラフにある場合だけ辺を張る
+//
+fn add_after_edge(
+    render_graph: &mut RenderGraph
+    , sub_graph: impl RenderSubGraph
+    , after: InternedRenderLabel
+    , label: InternedRenderLabel
+) {
+    let Some(graph) = render_graph.get_sub_graph_mut(sub_graph) else {
+        return;
+    };
+    if graph.get_node_state(after).is_err() {
+        return;
+    }
+    match graph.try_add_node_edge(after, label) {
+        Ok(()) | Err(RenderGraphError::EdgeAlreadyExists(_)) => {}
+        , Err(error) => panic!("{error:?}")
+    }
+}
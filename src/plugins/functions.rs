@@ -1 +1,11 @@
-pub mod shader;
\ No newline at end of file
+pub mod shader;
+pub mod blue_noise;
+pub mod error_diffusion;
+pub mod palette;
+pub mod bayer;
+pub mod view;
+pub mod outline;
+pub mod post_effect;
+pub mod graph;
+pub mod bind_group_cache;
+pub mod status;
\ No newline at end of file
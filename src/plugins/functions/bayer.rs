@@ -0,0 +1,82 @@
+use bevy::{
+    prelude::*
+    , asset::RenderAssetUsages
+    , render::render_resource::{Extent3d, TextureDimension, TextureFormat}
+};
+
+//
+// 2^order x 2^order のベイヤー行列（0..4^order の順位）を再帰的に生成する
+// M(2n) = | 4M(n)     4M(n) + 2 |
+//         | 4M(n) + 3 4M(n) + 1 |
+//
+pub fn bayer_matrix(order: u32) -> Vec<u32> {
+    if order == 0 {
+        return vec![0];
+    }
+
+    let half = 1_usize << (order - 1);
+    let size = half * 2;
+    let previous = bayer_matrix(order - 1);
+    let mut matrix = vec![0_u32; size * size];
+    for y in 0..size {
+        for x in 0..size {
+            let base = 4 * previous[(y % half) * half + (x % half)];
+            let offset = match (x < half, y < half) {
+                (true, true)    => 0
+                , (false, true) => 2
+                , (true, false) => 3
+                , (false, false) => 1
+            };
+            matrix[y * size + x] = base + offset;
+        }
+    }
+    matrix
+}
+
+//
+// ベイヤー行列を 0.0～1.0 に正規化した閾値のテクスチャ画像として生成する
+// 2^order の行列の左上 n x n は n x n の行列を正規化したものと一致するため、
+// 最大サイズの画像1枚を座標 % n で参照すればすべてのサイズの行列として使える
+//
+pub fn bayer_image(order: u32) -> Image {
+    let size  = 1_u32 << order;
+    let total = (size * size) as f32;
+    let data = bayer_matrix(order)
+        .into_iter()
+        .flat_map(|rank| (rank as f32 / total).to_le_bytes())
+        .collect();
+
+    Image::new(
+        Extent3d { width: size, height: size, depth_or_array_layers: 1 }
+        , TextureDimension::D2
+        , data
+        , TextureFormat::R32Float
+        , RenderAssetUsages::RENDER_WORLD
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::app::BAYER_MAX_ORDER;
+
+    #[test]
+    fn small_matrices_match_the_former_shader_constants() {
+        assert_eq!(bayer_matrix(1), vec![0, 2, 3, 1]);
+        assert_eq!(bayer_matrix(2), vec![
+             0,  8,  2, 10,
+            12,  4, 14,  6,
+             3, 11,  1,  9,
+            15,  7, 13,  5
+        ]);
+    }
+
+    #[test]
+    fn matrices_are_a_permutation_of_every_rank() {
+        for order in 0..=BAYER_MAX_ORDER {
+            let mut matrix = bayer_matrix(order);
+            matrix.sort_unstable();
+            assert_eq!(matrix, (0..1_u32 << (2 * order)).collect::<Vec<_>>(), "order {order}");
+        }
+    }
+}
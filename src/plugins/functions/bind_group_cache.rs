@@ -0,0 +1,133 @@
+use bevy::{
+    prelude::*
+    , core_pipeline::prepass::ViewPrepassTextures
+    , diagnostic::Diagnostics
+    , platform::collections::HashMap
+    , render::{
+        extract_component::ComponentUniforms
+        , render_asset::RenderAssets
+        , render_resource::*
+        , renderer::RenderDevice
+        , texture::{FallbackImage, GpuImage}
+        , view::ViewTarget
+    }
+};
+use crate::plugins::structs::components::{PostProcessPalette, PostProcessSettings, PostProcessUniform};
+use crate::plugins::structs::post_processes::*;
+use crate::plugins::structs::palette::GpuPalette;
+use crate::plugins::structs::threshold_map::GpuThresholdMap;
+use crate::plugins::structs::bind_group_cache::*;
+
+//
+// ビューごとにポストプロセスのバインドグループを用意する
+// バインドするリソースが前のフレームと同じ場合はキャッシュしたものを使い回す
+// 入力のテクスチャはノードの実行時まで決まらないので2枚のメインテクスチャの両方について用意する
+//
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn prepare_post_process_bind_groups(
+    render_device: Res<RenderDevice>
+    , pipeline: Res<PostProcessPipeline>
+    , settings_uniforms: Res<ComponentUniforms<PostProcessUniform>>
+    , gpu_images: Res<RenderAssets<GpuImage>>
+    , gpu_palettes: Res<RenderAssets<GpuPalette>>
+    , gpu_threshold_maps: Res<RenderAssets<GpuThresholdMap>>
+    , fallback_image: Res<FallbackImage>
+    , blue_noise: Res<BlueNoiseTexture>
+    , bayer: Res<BayerTexture>
+    , counter: Res<PostProcessBindGroupCounter>
+    , mut cache: ResMut<PostProcessBindGroupCache>
+    , views: Query<(Entity, &ViewTarget, &PostProcessSettings, &Msaa, Option<&PostProcessPalette>, Option<&ViewPrepassTextures>)>
+) {
+    let (Some(settings_buffer), Some(settings_binding)) = (settings_uniforms.uniforms().buffer(), settings_uniforms.uniforms().binding()) else {
+        counter.set(0);
+        return;
+    };
+    let mut created = 0;
+    let mut previous = std::mem::take(&mut cache.views);
+
+    // 閾値テクスチャがまだ GPU に転送されていない場合は代替の画像を使う
+    let fallback_view = &fallback_image.d2.texture_view;
+    let blue_noise_view = gpu_images.get(&blue_noise.0)
+        .map_or(fallback_view, |image| &image.texture_view);
+    let bayer_view = gpu_images.get(&bayer.0)
+        .map_or(fallback_view, |image| &image.texture_view);
+
+    let mut views_bind_groups = HashMap::default();
+    for (entity, view_target, settings, msaa, palette, prepass_textures) in &views {
+        // 閾値マップが使われるかどうかは THRESHOLD_MAP のシェーダー定義で決まるので、未指定の場合は代替の画像を渡しておく
+        let threshold_map_view = settings.threshold_map.as_ref()
+            .and_then(|threshold_map| gpu_threshold_maps.get(threshold_map))
+            .map_or(fallback_view, |threshold_map| &threshold_map.view);
+        // パレットが未指定か読み込み中の場合は空のパレットを使う
+        let palette_view = palette
+            .and_then(|palette| gpu_palettes.get(&palette.0))
+            .map_or(&pipeline.empty_palette, |palette| &palette.view);
+        // プリパスのテクスチャは深度・法線のエッジを検出し、かつ MSAA が無効の場合だけ使う
+        // ※ MSAA が有効なカメラのプリパスはマルチサンプルのテクスチャになり、バインディングの形式と合わない
+        //    （require_edge_prepasses が MSAA を無効にするのはエッジで使う場合だけで、無効になるまでにも1フレームかかる）
+        let prepass_textures = prepass_textures.filter(|_| msaa.samples() == 1);
+        // 深度プリパスを使わないビューでは代替の深度テクスチャを使う
+        let depth_view = prepass_textures
+            .filter(|_| settings.edges.depth.is_some())
+            .and_then(|prepass_textures| prepass_textures.depth_view())
+            .unwrap_or(&pipeline.empty_depth);
+        // 法線プリパスを使わないビューでは代替の画像（全画素同じ値なので法線エッジは検出されない）を使う
+        let normal_view = prepass_textures
+            .filter(|_| settings.edges.normal.is_some())
+            .and_then(|prepass_textures| prepass_textures.normal_view())
+            .unwrap_or(fallback_view);
+
+        let mut cached = previous.remove(&entity).unwrap_or_default();
+        let bind_groups = [view_target.main_texture_view(), view_target.main_texture_other_view()]
+            .into_iter()
+            .map(|source| {
+                let key = PostProcessBindGroupKey {
+                    source: source.id()
+                    , settings: settings_buffer.id()
+                    , blue_noise: blue_noise_view.id()
+                    , palette: palette_view.id()
+                    , bayer: bayer_view.id()
+                    , threshold_map: threshold_map_view.id()
+                    , depth: depth_view.id()
+                    , normal: normal_view.id()
+                };
+                if let Some(index) = cached.iter().position(|cached| cached.key == key) {
+                    return cached.swap_remove(index);
+                }
+
+                created += 1;
+                let bind_group = render_device.create_bind_group(
+                    "post_process_bind_group"
+                    , &pipeline.layout
+                    , &BindGroupEntries::sequential((
+                        source
+                        , &pipeline.sampler
+                        , settings_binding.clone()
+                        , blue_noise_view
+                        , palette_view
+                        , bayer_view
+                        , threshold_map_view
+                        , depth_view
+                        , normal_view
+                    ))
+                );
+                CachedPostProcessBindGroup { key, bind_group }
+            })
+            .collect::<Vec<_>>();
+        views_bind_groups.insert(entity, bind_groups);
+    }
+
+    // このフレームにないビューのキャッシュは previous と一緒に破棄される
+    cache.views = views_bind_groups;
+    counter.set(created);
+}
+
+//
+// レンダーワールドで作成したバインドグループの数を診断に記録する
+//
+pub fn measure_post_process_bind_groups(
+    counter: Res<PostProcessBindGroupCounter>
+    , mut diagnostics: Diagnostics
+) {
+    diagnostics.add_measurement(&POST_PROCESS_BIND_GROUPS_CREATED, || counter.get() as f64);
+}
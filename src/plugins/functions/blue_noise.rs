@@ -0,0 +1,199 @@
+use bevy::{
+    prelude::*
+    , asset::RenderAssetUsages
+    , render::render_resource::{Extent3d, TextureDimension, TextureFormat}
+    , tasks::{block_on, poll_once}
+};
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+use crate::plugins::structs::post_processes::{BlueNoiseTask, BlueNoiseTexture};
+
+// 初期パターンで1にするピクセルの割合
+const INITIAL_DENSITY: f64 = 0.1;
+// エネルギー計算に使うガウス関数のシグマ（Ulichney の推奨値）
+const SIGMA: f64 = 1.5;
+
+//
+// Void-and-Cluster 法でブルーノイズの閾値マップ（ランク配列）を生成する
+// 戻り値は size * size の各ピクセルに 0..size*size の順位を割り当てた配列
+// 参考
+// R. Ulichney, "The void-and-cluster method for dither array generation", 1993
+//
+// ※ 計算量は O(n^2)（n = size * size）なので 256 以上はかなり時間がかかる点に注意（BlueNoiseTexture は別スレッドで生成する）
+//
+pub fn void_and_cluster(size: u32, seed: u64) -> Vec<u32> {
+    let size  = size.max(1) as usize;
+    let total = size * size;
+    let kernel = gaussian_kernel(size);
+
+    let mut pattern = vec![false; total];
+    let mut energy  = vec![0.0_f64; total];
+    let mut rng = SmallRng::seed_from_u64(seed);
+
+    // 初期パターン（ランダムに点を打つ）
+    let initial_ones = ((total as f64 * INITIAL_DENSITY) as usize).clamp(1, total);
+    let mut ones = 0;
+    while ones < initial_ones {
+        let index = rng.random_range(0..total);
+        if !pattern[index] {
+            toggle(&mut pattern, &mut energy, &kernel, size, index);
+            ones += 1;
+        }
+    }
+
+    // 最も密な点を最も疎な場所へ移動させ続けて初期パターンを均一にする
+    if ones < total {
+        loop {
+            let cluster = tightest_cluster(&pattern, &energy);
+            toggle(&mut pattern, &mut energy, &kernel, size, cluster);
+            let void = largest_void(&pattern, &energy);
+            toggle(&mut pattern, &mut energy, &kernel, size, void);
+            if void == cluster { break; }
+        }
+    }
+
+    let mut ranks = vec![0_u32; total];
+    let prototype_pattern = pattern.clone();
+    let prototype_energy  = energy.clone();
+
+    // フェーズ1: 初期パターンから密な点を順に取り除いて順位を降順に振る
+    let mut rank = ones;
+    while rank > 0 {
+        let cluster = tightest_cluster(&pattern, &energy);
+        toggle(&mut pattern, &mut energy, &kernel, size, cluster);
+        rank -= 1;
+        ranks[cluster] = rank as u32;
+    }
+
+    // フェーズ2, 3: 初期パターンから疎な場所を順に埋めて順位を昇順に振る
+    // ※ 半分を超えた後（フェーズ3）の「0 の最も密な点」はカーネルの総和が一定なので「1 の最も疎な場所」と一致する
+    pattern = prototype_pattern;
+    energy  = prototype_energy;
+    for rank in ones..total {
+        let void = largest_void(&pattern, &energy);
+        toggle(&mut pattern, &mut energy, &kernel, size, void);
+        ranks[void] = rank as u32;
+    }
+
+    ranks
+}
+
+//
+// ブルーノイズの閾値マップを R8 のテクスチャ画像として生成する
+//
+pub fn blue_noise_image(size: u32, seed: u64) -> Image {
+    let size  = size.max(1);
+    let total = (size * size) as f64;
+    let data = void_and_cluster(size, seed)
+        .into_iter()
+        .map(|rank| (((rank as f64 + 0.5) / total) * 255.0).round() as u8)
+        .collect();
+
+    Image::new(
+        Extent3d { width: size, height: size, depth_or_array_layers: 1 }
+        , TextureDimension::D2
+        , data
+        , TextureFormat::R8Unorm
+        , RenderAssetUsages::RENDER_WORLD
+    )
+}
+
+//
+// ブルーノイズの生成が終わるまで使う 1x1 の代替画像（閾値 0.5 で固定）
+//
+pub fn blue_noise_placeholder_image() -> Image {
+    Image::new(
+        Extent3d { width: 1, height: 1, depth_or_array_layers: 1 }
+        , TextureDimension::D2
+        , vec![128]
+        , TextureFormat::R8Unorm
+        , RenderAssetUsages::RENDER_WORLD
+    )
+}
+
+//
+// 別スレッドで生成していたブルーノイズの閾値テクスチャが出来上がったら代替画像と差し替える
+// 同じハンドルのまま中身を入れ替えるので、バインドグループはテクスチャの変更を検知して作り直される
+//
+pub fn finish_blue_noise_texture(
+    mut commands: Commands
+    , task: Option<ResMut<BlueNoiseTask>>
+    , blue_noise: Res<BlueNoiseTexture>
+    , mut images: ResMut<Assets<Image>>
+) {
+    let Some(mut task) = task else {
+        return;
+    };
+    let Some(image) = block_on(poll_once(&mut task.0)) else {
+        return;
+    };
+
+    images.insert(&blue_noise.0, image);
+    commands.remove_resource::<BlueNoiseTask>();
+}
+
+//
+// トーラス上（上下左右がループ）での距離に応じたガウス関数の値を事前計算する
+//
+fn gaussian_kernel(size: usize) -> Vec<f64> {
+    let mut kernel = vec![0.0_f64; size * size];
+    for y in 0..size {
+        for x in 0..size {
+            let dx = x.min(size - x) as f64;
+            let dy = y.min(size - y) as f64;
+            kernel[y * size + x] = (-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA)).exp();
+        }
+    }
+    kernel
+}
+
+//
+// ピクセルの 0/1 を反転してエネルギーを更新する
+//
+fn toggle(pattern: &mut [bool], energy: &mut [f64], kernel: &[f64], size: usize, index: usize) {
+    pattern[index] = !pattern[index];
+    let sign = if pattern[index] { 1.0 } else { -1.0 };
+    let (px, py) = (index % size, index / size);
+    for y in 0..size {
+        let ky = (y + size - py) % size;
+        for x in 0..size {
+            let kx = (x + size - px) % size;
+            energy[y * size + x] += sign * kernel[ky * size + kx];
+        }
+    }
+}
+
+// 1 のピクセルの中で最もエネルギーが高い（密集している）位置
+fn tightest_cluster(pattern: &[bool], energy: &[f64]) -> usize {
+    (0..pattern.len())
+        .filter(|&i| pattern[i])
+        .max_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+        .unwrap_or(0)
+}
+
+// 0 のピクセルの中で最もエネルギーが低い（空いている）位置
+fn largest_void(pattern: &[bool], energy: &[f64]) -> usize {
+    (0..pattern.len())
+        .filter(|&i| !pattern[i])
+        .min_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::app::DEFAULT_BLUE_NOISE_SEED;
+
+    #[test]
+    fn ranks_are_a_permutation_of_every_pixel() {
+        for size in [1, 2, 8, 16] {
+            let mut ranks = void_and_cluster(size, DEFAULT_BLUE_NOISE_SEED);
+            ranks.sort_unstable();
+            assert_eq!(ranks, (0..size * size).collect::<Vec<_>>(), "size {size}");
+        }
+    }
+
+    #[test]
+    fn same_size_and_seed_give_the_same_ranks() {
+        assert_eq!(void_and_cluster(16, DEFAULT_BLUE_NOISE_SEED), void_and_cluster(16, DEFAULT_BLUE_NOISE_SEED));
+    }
+}
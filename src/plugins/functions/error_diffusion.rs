@@ -0,0 +1,222 @@
+use bevy::{
+    prelude::*
+    , render::{
+        render_asset::RenderAssets
+        , render_resource::*
+        , renderer::{RenderDevice, RenderQueue}
+        , view::ViewTarget
+    }
+};
+use crate::consts::app::*;
+use crate::plugins::structs::components::{ErrorDiffusionBand, ErrorDiffusionParams, PostProcessPalette, PostProcessSettings};
+use crate::plugins::structs::error_diffusion::*;
+use crate::plugins::structs::palette::GpuPalette;
+use crate::plugins::structs::post_processes::PostProcessPipeline;
+
+//
+// 誤差拡散を使うビューに作業用のバッファとバインドグループを用意する
+// 画面サイズが変わった時はバッファを作り直し、誤差拡散を使わなくなったビューからは取り除く
+// バインドグループはバインドするリソースが前のフレームと同じ場合は使い回す
+//
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn prepare_error_diffusion_buffers(
+    mut commands: Commands
+    , render_device: Res<RenderDevice>
+    , render_queue: Res<RenderQueue>
+    , pipeline: Res<ErrorDiffusionPipeline>
+    , post_process_pipeline: Res<PostProcessPipeline>
+    , gpu_palettes: Res<RenderAssets<GpuPalette>>
+    , mut views: Query<(Entity, &ViewTarget, &PostProcessSettings, Option<&PostProcessPalette>, Option<&mut ErrorDiffusionBuffers>)>
+) {
+    for (entity, view_target, settings, palette, buffers) in &mut views {
+        let Some((kernel, serpentine)) = settings.error_diffusion() else {
+            if buffers.is_some() {
+                commands.entity(entity).remove::<ErrorDiffusionBuffers>();
+            }
+            continue;
+        };
+
+        let size = view_target.main_texture().size();
+        let size = UVec2::new(size.width, size.height);
+        let params = ErrorDiffusionParams {
+            width: size.x
+            , height: size.y
+            , kernel: kernel.raw()
+            , serpentine: serpentine as u32
+            , monochrome: settings.dither.monochrome as u32
+            , ..default()
+        };
+        // パレットが未指定か読み込み中の場合は空のパレット（チャンネルごとの2値）を使う
+        let palette_view = palette
+            .and_then(|palette| gpu_palettes.get(&palette.0))
+            .map_or(&post_process_pipeline.empty_palette, |palette| &palette.view);
+
+        match buffers {
+            Some(mut buffers) if buffers.size == size => {
+                buffers.params.set(params);
+                buffers.params.write_buffer(&render_device, &render_queue);
+                write_error_diffusion_bands(&render_device, &render_queue, &mut buffers, serpentine);
+                let previous = std::mem::take(&mut buffers.bind_groups);
+                buffers.bind_groups = error_diffusion_bind_groups(&render_device, &pipeline, &buffers, view_target, palette_view, previous);
+            }
+            _ => {
+                let pixels = render_device.create_buffer(&BufferDescriptor {
+                    label: Some("error_diffusion_pixels")
+                    , size: (size.x as u64) * (size.y as u64) * 16
+                    , usage: BufferUsages::STORAGE | BufferUsages::COPY_DST
+                    , mapped_at_creation: false
+                });
+                let mut uniform = UniformBuffer::from(params);
+                uniform.write_buffer(&render_device, &render_queue);
+                let mut buffers = ErrorDiffusionBuffers {
+                    pixels
+                    , params: uniform
+                    , bands: DynamicUniformBuffer::default()
+                    , band_offsets: Vec::new()
+                    , size
+                    , bind_groups: Vec::new()
+                };
+                write_error_diffusion_bands(&render_device, &render_queue, &mut buffers, serpentine);
+                buffers.bind_groups = error_diffusion_bind_groups(&render_device, &pipeline, &buffers, view_target, palette_view, Vec::new());
+                commands.entity(entity).insert(buffers);
+            }
+        }
+    }
+}
+
+//
+// 誤差拡散の1回のディスパッチで処理する行のバンドに分ける
+// 1回のディスパッチのステップ数（幅 + 行の遅れ * (行数 - 1)）が ERROR_DIFFUSION_MAX_STEPS を超えないようにする
+// 蛇行走査では下の行が1つ上の行の終わりを待つ（遅れが幅と同じ）ので、バンドの行数が少なくなる
+// ※ 1行だけで上限を超える幅の場合は1行ずつ処理する
+//
+pub fn error_diffusion_bands(size: UVec2, serpentine: bool) -> Vec<ErrorDiffusionBand> {
+    let lag = if serpentine { size.x } else { ERROR_DIFFUSION_ROW_LAG }.max(1);
+    let rows = (ERROR_DIFFUSION_MAX_STEPS.saturating_sub(size.x) / lag + 1).min(ERROR_DIFFUSION_WORKGROUP_ROWS);
+    (0..size.y)
+        .step_by(rows as usize)
+        .map(|first_row| ErrorDiffusionBand { first_row, row_count: rows.min(size.y - first_row), ..default() })
+        .collect()
+}
+
+// バンドを書き込み、ディスパッチごとの動的オフセットを記録する
+fn write_error_diffusion_bands(
+    render_device: &RenderDevice
+    , render_queue: &RenderQueue
+    , buffers: &mut ErrorDiffusionBuffers
+    , serpentine: bool
+) {
+    buffers.bands.clear();
+    buffers.band_offsets = error_diffusion_bands(buffers.size, serpentine)
+        .into_iter()
+        .map(|band| buffers.bands.push(&band))
+        .collect();
+    buffers.bands.write_buffer(render_device, render_queue);
+}
+
+//
+// 2枚のメインテクスチャのそれぞれを入力にした誤差拡散のバインドグループを用意する
+// previous に同じリソースのものがあればそれを使い回す
+//
+fn error_diffusion_bind_groups(
+    render_device: &RenderDevice
+    , pipeline: &ErrorDiffusionPipeline
+    , buffers: &ErrorDiffusionBuffers
+    , view_target: &ViewTarget
+    , palette_view: &TextureView
+    , mut previous: Vec<CachedErrorDiffusionBindGroups>
+) -> Vec<CachedErrorDiffusionBindGroups> {
+    let (Some(params_buffer), Some(params_binding)) = (buffers.params.buffer(), buffers.params.binding()) else {
+        return Vec::new();
+    };
+    let (Some(bands_buffer), Some(bands_binding)) = (buffers.bands.buffer(), buffers.bands.binding()) else {
+        return Vec::new();
+    };
+
+    [view_target.main_texture_view(), view_target.main_texture_other_view()]
+        .into_iter()
+        .map(|source| {
+            let key = ErrorDiffusionBindGroupKey {
+                source: source.id()
+                , pixels: buffers.pixels.id()
+                , params: params_buffer.id()
+                , palette: palette_view.id()
+                , bands: bands_buffer.id()
+            };
+            if let Some(index) = previous.iter().position(|cached| cached.key == key) {
+                return previous.swap_remove(index);
+            }
+
+            let diffuse = render_device.create_bind_group(
+                "error_diffusion_bind_group"
+                , &pipeline.diffuse_layout
+                , &BindGroupEntries::sequential((
+                    source
+                    , buffers.pixels.as_entire_binding()
+                    , params_binding.clone()
+                    , palette_view
+                    , bands_binding.clone()
+                ))
+            );
+            let blit = render_device.create_bind_group(
+                "error_diffusion_blit_bind_group"
+                , &pipeline.blit_layout
+                , &BindGroupEntries::sequential((
+                    buffers.pixels.as_entire_binding()
+                    , params_binding.clone()
+                ))
+            );
+            CachedErrorDiffusionBindGroups { key, diffuse, blit }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(width: u32, band: &ErrorDiffusionBand, serpentine: bool) -> u32 {
+        let lag = if serpentine { width } else { ERROR_DIFFUSION_ROW_LAG };
+        width + lag * (band.row_count - 1)
+    }
+
+    #[test]
+    fn bands_cover_every_row_once_in_order() {
+        for serpentine in [false, true] {
+            let size = UVec2::new(3840, 2160);
+            let bands = error_diffusion_bands(size, serpentine);
+            let mut next_row = 0;
+            for band in &bands {
+                assert_eq!(band.first_row, next_row);
+                assert!(band.row_count >= 1 && band.row_count <= ERROR_DIFFUSION_WORKGROUP_ROWS);
+                next_row += band.row_count;
+            }
+            assert_eq!(next_row, size.y);
+        }
+    }
+
+    #[test]
+    fn bands_stay_within_the_step_limit() {
+        for serpentine in [false, true] {
+            for size in [UVec2::new(1280, 720), UVec2::new(3840, 2160), UVec2::new(7680, 4320)] {
+                for band in error_diffusion_bands(size, serpentine) {
+                    assert!(steps(size.x, &band, serpentine) <= ERROR_DIFFUSION_MAX_STEPS, "{size} serpentine: {serpentine}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn serpentine_bands_are_smaller() {
+        let size = UVec2::new(1920, 1080);
+        assert_eq!(error_diffusion_bands(size, false)[0].row_count, ERROR_DIFFUSION_WORKGROUP_ROWS);
+        assert!(error_diffusion_bands(size, true)[0].row_count < ERROR_DIFFUSION_WORKGROUP_ROWS);
+    }
+
+    #[test]
+    fn rows_wider_than_the_limit_get_their_own_band() {
+        let size = UVec2::new(ERROR_DIFFUSION_MAX_STEPS + 1, 3);
+        assert!(error_diffusion_bands(size, true).iter().all(|band| band.row_count == 1));
+        assert!(error_diffusion_bands(UVec2::new(640, 0), false).is_empty());
+    }
+}
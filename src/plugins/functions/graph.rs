@@ -0,0 +1,176 @@
+use bevy::{
+    prelude::*
+    , render::render_graph::{
+        InternedRenderLabel
+        , RenderGraph
+        , RenderGraphError
+        , RenderSubGraph
+    }
+};
+use crate::plugins::structs::placement::*;
+
+//
+// ポストプロセスのノードの列を placement の位置に繋ぐ
+// 前のノードから列の先頭へ、列のすべてのノードから後のノードへ辺を張る
+// 前後のノードがグラフにない場合（UI のプラグインを使っていないなど）はトーンマッピングの後に置く
+// ※ 他のプラグインのノードが揃ってから呼ぶこと（Plugin::finish から呼ぶ）
+//
+pub fn add_placement_edges(
+    render_graph: &mut RenderGraph
+    , sub_graph: impl RenderSubGraph
+    , chain: &[InternedRenderLabel]
+    , placement: PostProcessPlacement
+    , anchors: fn(&PostProcessPlacement) -> PlacementAnchors
+) {
+    let sub_graph = sub_graph.intern();
+    let Some(graph) = render_graph.get_sub_graph_mut(sub_graph) else {
+        return;
+    };
+    let Some(first) = chain.first() else {
+        return;
+    };
+
+    let existing = |labels: Vec<InternedRenderLabel>| labels
+        .into_iter()
+        .filter(|label| graph.get_node_state(*label).is_ok())
+        .collect::<Vec<_>>();
+    let PlacementAnchors { after, before } = anchors(&placement);
+    let (mut after, mut before) = (existing(after), existing(before));
+    if after.is_empty() || before.is_empty() {
+        warn!("{placement:?} is not available in {sub_graph:?}, the post process is placed after tonemapping");
+        let PlacementAnchors { after: fallback_after, before: fallback_before } = anchors(&PostProcessPlacement::AfterTonemapping);
+        (after, before) = (existing(fallback_after), existing(fallback_before));
+    }
+
+    let edges = after
+        .iter()
+        .map(|label| (*label, *first))
+        .chain(chain.iter().flat_map(|node| before.iter().map(|label| (*node, *label))));
+    for (output, input) in edges {
+        match graph.try_add_node_edge(output, input) {
+            Ok(()) | Err(RenderGraphError::EdgeAlreadyExists(_)) => {}
+            , Err(error) => panic!("{error:?}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        core_pipeline::{
+            core_2d::graph::{Core2d, Node2d}
+            , core_3d::graph::{Core3d, Node3d}
+        }
+        , render::render_graph::{Edge, EmptyNode, RenderLabel}
+    };
+    use super::*;
+
+    const PLACEMENTS: [PostProcessPlacement; 4] = [
+        PostProcessPlacement::BeforeTonemapping
+        , PostProcessPlacement::AfterTonemapping
+        , PostProcessPlacement::AfterAntiAliasing
+        , PostProcessPlacement::AfterUi
+    ];
+
+    #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+    struct FirstLabel;
+
+    #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+    struct SecondLabel;
+
+    fn chain() -> Vec<InternedRenderLabel> {
+        vec![FirstLabel.intern(), SecondLabel.intern()]
+    }
+
+    // anchors に含まれるノードと列のノードを持つサブグラフだけのグラフ
+    fn render_graph(sub_graph: impl RenderSubGraph, anchors: &[InternedRenderLabel]) -> RenderGraph {
+        let mut graph = RenderGraph::default();
+        for label in anchors.iter().copied().chain(chain()) {
+            if graph.get_node_state(label).is_err() {
+                graph.add_node(label, EmptyNode);
+            }
+        }
+        let mut render_graph = RenderGraph::default();
+        render_graph.add_sub_graph(sub_graph, graph);
+        render_graph
+    }
+
+    // すべての placement の前後のノード
+    fn all_anchors(anchors: fn(&PostProcessPlacement) -> PlacementAnchors) -> Vec<InternedRenderLabel> {
+        PLACEMENTS
+            .iter()
+            .flat_map(|placement| {
+                let PlacementAnchors { after, before } = anchors(placement);
+                after.into_iter().chain(before)
+            })
+            .collect()
+    }
+
+    fn has_edge(graph: &RenderGraph, output: InternedRenderLabel, input: InternedRenderLabel) -> bool {
+        graph.has_edge(&Edge::NodeEdge { input_node: input, output_node: output })
+    }
+
+    // 列の先頭が after のすべてのノードの後に、列のすべてのノードが before のすべてのノードの前にあること
+    fn assert_placed(graph: &RenderGraph, anchors: PlacementAnchors) {
+        for after in anchors.after {
+            assert!(has_edge(graph, after, FirstLabel.intern()), "missing edge {after:?} -> FirstLabel");
+        }
+        for node in chain() {
+            for before in &anchors.before {
+                assert!(has_edge(graph, node, *before), "missing edge {node:?} -> {before:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn every_placement_is_ordered_between_its_3d_anchors() {
+        for placement in PLACEMENTS {
+            let mut render_graph = render_graph(Core3d, &all_anchors(PostProcessPlacement::anchors_3d));
+            add_placement_edges(&mut render_graph, Core3d, &chain(), placement, PostProcessPlacement::anchors_3d);
+            assert_placed(render_graph.sub_graph(Core3d), placement.anchors_3d());
+        }
+    }
+
+    #[test]
+    fn every_placement_is_ordered_between_its_2d_anchors() {
+        for placement in PLACEMENTS {
+            let mut render_graph = render_graph(Core2d, &all_anchors(PostProcessPlacement::anchors_2d));
+            add_placement_edges(&mut render_graph, Core2d, &chain(), placement, PostProcessPlacement::anchors_2d);
+            assert_placed(render_graph.sub_graph(Core2d), placement.anchors_2d());
+        }
+    }
+
+    #[test]
+    fn missing_anchors_fall_back_to_after_tonemapping() {
+        // UI のノードがないグラフ
+        let anchors = [Node3d::Tonemapping.intern(), Node3d::EndMainPassPostProcessing.intern(), Node3d::Upscaling.intern()];
+        let mut render_graph = render_graph(Core3d, &anchors);
+        add_placement_edges(&mut render_graph, Core3d, &chain(), PostProcessPlacement::AfterUi, PostProcessPlacement::anchors_3d);
+
+        let graph = render_graph.sub_graph(Core3d);
+        assert_placed(graph, PostProcessPlacement::AfterTonemapping.anchors_3d());
+        assert!(!has_edge(graph, SecondLabel.intern(), Node3d::Upscaling.intern()));
+    }
+
+    #[test]
+    fn existing_edges_are_kept() {
+        let anchors = [Node2d::Tonemapping.intern(), Node2d::EndMainPassPostProcessing.intern()];
+        let mut render_graph = render_graph(Core2d, &anchors);
+        render_graph.sub_graph_mut(Core2d).add_node_edge(Node2d::Tonemapping, FirstLabel);
+
+        add_placement_edges(&mut render_graph, Core2d, &chain(), PostProcessPlacement::AfterTonemapping, PostProcessPlacement::anchors_2d);
+        add_placement_edges(&mut render_graph, Core2d, &chain(), PostProcessPlacement::AfterTonemapping, PostProcessPlacement::anchors_2d);
+        assert_placed(render_graph.sub_graph(Core2d), PostProcessPlacement::AfterTonemapping.anchors_2d());
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidNode")]
+    fn nodes_missing_from_the_graph_panic() {
+        let anchors = [Node3d::Tonemapping.intern(), Node3d::EndMainPassPostProcessing.intern()];
+        let mut render_graph = render_graph(Core3d, &anchors);
+
+        #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+        struct MissingLabel;
+        add_placement_edges(&mut render_graph, Core3d, &[MissingLabel.intern()], PostProcessPlacement::AfterTonemapping, PostProcessPlacement::anchors_3d);
+    }
+}
@@ -0,0 +1,274 @@
+use bevy::{
+    prelude::*
+    , render::{
+        mesh::RenderMesh
+        , render_asset::RenderAssets
+        , render_resource::*
+        , renderer::{RenderDevice, RenderQueue}
+        , texture::TextureCache
+        , view::{ExtractedView, ViewTarget}
+        , Extract
+    }
+};
+use crate::consts::app::*;
+use crate::plugins::structs::components::PostProcessSettings;
+use crate::plugins::structs::outline::*;
+use crate::plugins::structs::settings::OutlineStyle;
+
+//
+// Outlined が付いたエンティティの子孫のメッシュに同じ設定を伝播する
+// Outlined が変更された時と、glTF のシーンの読み込みなどで子が追加・変更された時だけ子孫をたどる
+// 子が変更された場合は最も近い祖先（自身を含む）の Outlined を伝播する
+// 子孫に直接付けられた Outlined（PropagatedOutline がないもの）はそちらを優先する
+//
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn propagate_outlines(
+    mut commands: Commands
+    , changed_roots: Query<(Entity, &Outlined), (Changed<Outlined>, Without<PropagatedOutline>)>
+    , changed_children: Query<Entity, Changed<Children>>
+    , roots: Query<&Outlined, Without<PropagatedOutline>>
+    , parents: Query<&ChildOf>
+    , children: Query<&Children>
+    , meshes: Query<(Option<&Outlined>, Has<PropagatedOutline>), With<Mesh3d>>
+    , mut removed: RemovedComponents<Outlined>
+) {
+    let mut propagate = |from: Entity, outlined: &Outlined| {
+        for descendant in children.iter_descendants(from) {
+            let Ok((current, is_propagated)) = meshes.get(descendant) else {
+                continue;
+            };
+            if current.is_some() && !is_propagated {
+                continue;
+            }
+            if current != Some(outlined) {
+                commands.entity(descendant).insert((*outlined, PropagatedOutline));
+            }
+        }
+    };
+
+    for (root, outlined) in &changed_roots {
+        propagate(root, outlined);
+    }
+    for entity in &changed_children {
+        let root = std::iter::once(entity)
+            .chain(parents.iter_ancestors(entity))
+            .find_map(|ancestor| roots.get(ancestor).ok());
+        if let Some(outlined) = root {
+            propagate(entity, outlined);
+        }
+    }
+
+    // 親の Outlined が取り除かれたら伝播したものも取り除く
+    for root in removed.read() {
+        for descendant in children.iter_descendants(root) {
+            if let Ok((_, true)) = meshes.get(descendant) {
+                commands.entity(descendant).remove::<(Outlined, PropagatedOutline)>();
+            }
+        }
+    }
+}
+
+//
+// Outlined のエンティティがある間、ポストプロセスを行う 3D のカメラの深度テクスチャをシェーダーから読めるようにする
+// アウトラインのマスクは他のオブジェクトに隠れた部分を除くためにシーンの深度を参照する
+// 追加したものは OutlineDepthTextureUsage に記録し、Outlined がなくなった時やポストプロセスを外した時に取り除く
+//
+pub fn require_outline_depth_textures(
+    mut commands: Commands
+    , outlined: Query<(), With<Outlined>>
+    , mut cameras: Query<(Entity, &mut Camera3d, Has<PostProcessSettings>, Has<OutlineDepthTextureUsage>)>
+) {
+    let has_outlines = !outlined.is_empty();
+    for (entity, mut camera, has_settings, added) in &mut cameras {
+        let usage = TextureUsages::from(camera.depth_texture_usages);
+        if has_outlines && has_settings {
+            if !usage.contains(TextureUsages::TEXTURE_BINDING) {
+                camera.depth_texture_usages = (usage | TextureUsages::TEXTURE_BINDING).into();
+                commands.entity(entity).insert(OutlineDepthTextureUsage);
+            }
+        } else if added {
+            camera.depth_texture_usages = (usage - TextureUsages::TEXTURE_BINDING).into();
+            commands.entity(entity).remove::<OutlineDepthTextureUsage>();
+        }
+    }
+}
+
+//
+// 表示されている Outlined のメッシュをレンダーワールドに抽出する
+//
+pub fn extract_outlines(
+    mut extracted: ResMut<ExtractedOutlines>
+    , outlines: Extract<Query<(&Outlined, &Mesh3d, &GlobalTransform, &ViewVisibility)>>
+) {
+    extracted.objects.clear();
+    for (outlined, mesh, transform, visibility) in &outlines {
+        if !visibility.get() {
+            continue;
+        }
+        extracted.objects.push(ExtractedOutline {
+            mesh: mesh.id()
+            , world_from_local: transform.compute_matrix()
+            , color: outlined.color.to_linear()
+            , width: outlined.width.max(0.0)
+        });
+    }
+}
+
+//
+// 抽出したメッシュごとにマスク用のパイプラインとユニフォームを用意する
+// ID はオブジェクトの並び順に 1 から振る（MAX_OUTLINED_OBJECTS を超えた分は描画しない）
+// パイプラインはポストプロセスを行う 3D のビューで使われる MSAA の有無の分だけ特殊化する
+//
+#[allow(clippy::too_many_arguments)]
+pub fn prepare_outline_objects(
+    render_device: Res<RenderDevice>
+    , render_queue: Res<RenderQueue>
+    , pipeline_cache: Res<PipelineCache>
+    , mask_pipeline: Res<OutlineMaskPipeline>
+    , mut specialized_pipelines: ResMut<SpecializedMeshPipelines<OutlineMaskPipeline>>
+    , meshes: Res<RenderAssets<RenderMesh>>
+    , extracted: Res<ExtractedOutlines>
+    , mut buffers: ResMut<OutlineBuffers>
+    , views: Query<&Msaa, (With<PostProcessSettings>, With<Camera3d>)>
+) {
+    if extracted.objects.len() > MAX_OUTLINED_OBJECTS {
+        warn_once!("{} outlined meshes, only the first {} are drawn", extracted.objects.len(), MAX_OUTLINED_OBJECTS);
+    }
+
+    let buffers = buffers.as_mut();
+    buffers.objects.clear();
+    buffers.draws.clear();
+    let mut settings = OutlineUniform::default();
+    let mut used_variants = [false; 2];
+    for msaa in &views {
+        used_variants[(msaa.samples() > 1) as usize] = true;
+    }
+
+    for (index, object) in extracted.objects.iter().take(MAX_OUTLINED_OBJECTS).enumerate() {
+        let Some(mesh) = meshes.get(object.mesh) else {
+            continue;
+        };
+        let mut pipeline_ids = [None; 2];
+        let mut failed = false;
+        for (multisampled, pipeline_id) in pipeline_ids.iter_mut().enumerate() {
+            if !used_variants[multisampled] {
+                continue;
+            }
+            let key = OutlineMaskPipelineKey { topology: mesh.primitive_topology(), multisampled: multisampled == 1 };
+            match specialized_pipelines.specialize(&pipeline_cache, &mask_pipeline, key, &mesh.layout) {
+                Ok(id) => *pipeline_id = Some(id)
+                , Err(error) => {
+                    error!("could not create outline mask pipeline: {error}");
+                    failed = true;
+                }
+            }
+        }
+        if failed {
+            continue;
+        }
+
+        let id = index as u32 + 1;
+        let object_offset = buffers.objects.push(&OutlineObjectUniform {
+            world_from_local: object.world_from_local
+            , id
+            , _pad_0: 0
+            , _pad_1: 0
+            , _pad_2: 0
+        });
+        buffers.draws.push(OutlineDraw { mesh: object.mesh, pipeline_ids, object_offset });
+
+        settings.objects[index] = OutlineEntry {
+            color: object.color.to_vec4()
+            , width: object.width
+            , ..default()
+        };
+        settings.count = id;
+        settings.max_width = settings.max_width.max(object.width);
+    }
+
+    buffers.objects.write_buffer(&render_device, &render_queue);
+    buffers.settings.set(settings);
+    buffers.settings.write_buffer(&render_device, &render_queue);
+}
+
+//
+// ポストプロセスを行う 3D のビューにマスクのテクスチャとビューのユニフォームを用意する（2D のビューには用意しない）
+// アウトラインの描き方がジャンプフラッディングの場合は距離場のテクスチャとパスごとのユニフォームも用意する
+// 描画するメッシュがない場合やポストプロセスを行わなくなったビューからは取り除く
+//
+#[allow(clippy::type_complexity)]
+pub fn prepare_outline_views(
+    mut commands: Commands
+    , render_device: Res<RenderDevice>
+    , render_queue: Res<RenderQueue>
+    , mut texture_cache: ResMut<TextureCache>
+    , mut buffers: ResMut<OutlineBuffers>
+    , views: Query<(Entity, &ExtractedView, &ViewTarget, &PostProcessSettings, &Msaa, Has<ViewOutlineMask>), With<Camera3d>>
+    , stale_views: Query<Entity, (With<ViewOutlineMask>, Or<(Without<PostProcessSettings>, Without<Camera3d>)>)>
+) {
+    for entity in &stale_views {
+        commands.entity(entity).remove::<ViewOutlineMask>();
+    }
+
+    buffers.views.clear();
+    buffers.jump_flood.clear();
+    if buffers.draws.is_empty() {
+        for (entity, .., has_mask) in &views {
+            if has_mask {
+                commands.entity(entity).remove::<ViewOutlineMask>();
+            }
+        }
+        return;
+    }
+    let max_width = buffers.settings.get().max_width;
+
+    for (entity, view, view_target, settings, msaa, _) in &views {
+        let size = view_target.main_texture().size();
+        let mut create_texture = |label, format, usage| texture_cache.get(&render_device, TextureDescriptor {
+            label: Some(label)
+            , size
+            , mip_level_count: 1
+            , sample_count: 1
+            , dimension: TextureDimension::D2
+            , format
+            , usage
+            , view_formats: &[]
+        });
+        let mask = create_texture(
+            "outline_mask_texture"
+            , TextureFormat::R32Uint
+            , TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING
+        );
+        let depth = create_texture(
+            "outline_mask_depth_texture"
+            , TextureFormat::Depth32Float
+            , TextureUsages::RENDER_ATTACHMENT
+        );
+
+        let clip_from_world = view.clip_from_world
+            .unwrap_or_else(|| view.clip_from_view * view.world_from_view.compute_matrix().inverse());
+        let view_offset = buffers.views.push(&OutlineViewUniform { clip_from_world });
+
+        let jump_flood = (settings.edges.outline_style == OutlineStyle::JumpFlood).then(|| {
+            let usage = TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING;
+            let textures = [
+                create_texture("jump_flood_texture_a", TextureFormat::Rg32Uint, usage)
+                , create_texture("jump_flood_texture_b", TextureFormat::Rg32Uint, usage)
+            ];
+
+            // 太さと光の長さまで届く最小の 2 の累乗の間隔から 1 まで半分ずつにする
+            let glow = settings.edges.outline_glow;
+            let reach = (max_width + glow).ceil().max(1.0) as u32;
+            let jump_offsets = std::iter::successors(Some(reach.next_power_of_two()), |step| (*step > 1).then_some(step / 2))
+                .map(|step| buffers.jump_flood.push(&JumpFloodUniform { step, glow, _pad_0: 0, _pad_1: 0 }))
+                .collect();
+            let outline_offset = buffers.jump_flood.push(&JumpFloodUniform { step: 0, glow, _pad_0: 0, _pad_1: 0 });
+            ViewJumpFlood { textures, jump_offsets, outline_offset }
+        });
+
+        commands.entity(entity).insert(ViewOutlineMask { mask, depth, view_offset, multisampled: msaa.samples() > 1, jump_flood });
+    }
+
+    buffers.views.write_buffer(&render_device, &render_queue);
+    buffers.jump_flood.write_buffer(&render_device, &render_queue);
+}
@@ -0,0 +1,230 @@
+use bevy::{
+    prelude::*
+    , render::{
+        render_resource::*
+        , renderer::{RenderDevice, RenderQueue}
+    }
+};
+use crate::consts::app::*;
+use crate::plugins::structs::palette::PaletteLoaderError;
+
+//
+// パレットの色をシェーダーに渡すテクスチャのデータに変換する
+// 1行目に線形 RGB（出力する色）、2行目に OKLab（近い色の検索に使う）を並べた Rgba32Float の画像データ
+//
+pub fn palette_texture_data(colors: &[Color]) -> Vec<u8> {
+    let linear = colors.iter().map(|color| {
+        let c = color.to_linear();
+        [c.red, c.green, c.blue, 1.0]
+    });
+    let oklab = colors.iter().map(|color| {
+        let c = Oklaba::from(*color);
+        [c.lightness, c.a, c.b, 1.0]
+    });
+
+    linear.chain(oklab)
+        .flatten()
+        .flat_map(f32::to_le_bytes)
+        .collect()
+}
+
+//
+// シェーダーに渡せる MAX_PALETTE_COLORS 色までに切り詰める
+//
+pub fn usable_palette_colors(colors: &[Color]) -> &[Color] {
+    if colors.len() > MAX_PALETTE_COLORS {
+        warn!("palette has {} colors, only the first {} are used", colors.len(), MAX_PALETTE_COLORS);
+    }
+    &colors[..colors.len().min(MAX_PALETTE_COLORS)]
+}
+
+//
+// パレットを1行目が線形 RGB、2行目が OKLab のテクスチャとして作成する
+//
+pub fn create_palette_texture(
+    render_device: &RenderDevice
+    , render_queue: &RenderQueue
+    , colors: &[Color]
+) -> Texture {
+    // 幅 0 のテクスチャは作れないので空の場合は黒1色にする（シェーダー側では2色未満は無効扱い）
+    let colors = if colors.is_empty() { &[Color::BLACK][..] } else { colors };
+    render_device.create_texture_with_data(
+        render_queue
+        , &TextureDescriptor {
+            label: Some("post_process_palette_texture")
+            , size: Extent3d { width: colors.len() as u32, height: 2, depth_or_array_layers: 1 }
+            , mip_level_count: 1
+            , sample_count: 1
+            , dimension: TextureDimension::D2
+            , format: TextureFormat::Rgba32Float
+            , usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST
+            , view_formats: &[]
+        }
+        , TextureDataOrder::LayerMajor
+        , &palette_texture_data(colors)
+    )
+}
+
+// "R G B" の形式の行を色に変換する（4列目以降の色名などは無視する）
+fn parse_rgb_line(line: &str, line_number: usize) -> Result<Color, PaletteLoaderError> {
+    let mut values = line.split_whitespace().take(3).map(|value| value.parse::<u8>());
+    match (values.next(), values.next(), values.next()) {
+        (Some(Ok(r)), Some(Ok(g)), Some(Ok(b))) => Ok(Color::srgb_u8(r, g, b))
+        , _ => Err(PaletteLoaderError::Parse { line: line_number, message: format!("expected \"R G B\", found \"{line}\"") })
+    }
+}
+
+//
+// GIMP パレット（.gpl）を解析する
+// "GIMP Palette" のヘッダーの後に Name: / Columns: / # コメント / "R G B 色名" の行が続く
+//
+pub fn parse_gpl(text: &str) -> Result<Vec<Color>, PaletteLoaderError> {
+    let mut lines = text.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim() == "GIMP Palette" => {}
+        _ => return Err(PaletteLoaderError::Parse { line: 1, message: "missing \"GIMP Palette\" header".into() })
+    }
+
+    lines
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| {
+            !line.is_empty()
+            && !line.starts_with('#')
+            && !line.starts_with("Name:")
+            && !line.starts_with("Columns:")
+        })
+        .map(|(line_number, line)| parse_rgb_line(line, line_number))
+        .collect()
+}
+
+//
+// HEX パレット（.hex）を解析する
+// 1行に1色 "RRGGBB"（先頭の # は省略可）
+//
+pub fn parse_hex(text: &str) -> Result<Vec<Color>, PaletteLoaderError> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(line_number, line)| {
+            Srgba::hex(line)
+                .map(Color::from)
+                .map_err(|error| PaletteLoaderError::Parse { line: line_number, message: error.to_string() })
+        })
+        .collect()
+}
+
+//
+// JASC パレット（.pal）を解析する
+// "JASC-PAL" / バージョン "0100" / 色数 の3行のヘッダーの後に "R G B" の行が色数分続く
+//
+pub fn parse_jasc_pal(text: &str) -> Result<Vec<Color>, PaletteLoaderError> {
+    let mut lines = text.lines().map(str::trim);
+    if lines.next() != Some("JASC-PAL") {
+        return Err(PaletteLoaderError::Parse { line: 1, message: "missing \"JASC-PAL\" header".into() });
+    }
+    if lines.next().is_none() {
+        return Err(PaletteLoaderError::Parse { line: 2, message: "missing version".into() });
+    }
+    let count = lines.next()
+        .and_then(|line| line.parse::<usize>().ok())
+        .ok_or(PaletteLoaderError::Parse { line: 3, message: "missing color count".into() })?;
+
+    let colors = lines
+        .enumerate()
+        .map(|(index, line)| (index + 4, line))
+        .filter(|(_, line)| !line.is_empty())
+        .take(count)
+        .map(|(line_number, line)| parse_rgb_line(line, line_number))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if colors.len() != count {
+        return Err(PaletteLoaderError::Parse { line: 3, message: format!("expected {count} colors, found {}", colors.len()) });
+    }
+    Ok(colors)
+}
+
+//
+// パレット画像（PNG）を解析する
+// 1ピクセル高さの横一列の画像を想定し、1行目のピクセルを左から順に色として使う
+//
+pub fn parse_png(bytes: &[u8]) -> Result<Vec<Color>, PaletteLoaderError> {
+    let image = image::load_from_memory_with_format(bytes, image::ImageFormat::Png)?.to_rgba8();
+    if image.height() != 1 {
+        warn!("palette image is {} pixels high, only the first row is used", image.height());
+    }
+
+    Ok((0..image.width())
+        .map(|x| {
+            let pixel = image.get_pixel(x, 0);
+            Color::srgba_u8(pixel[0], pixel[1], pixel[2], pixel[3])
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_parse_error(result: Result<Vec<Color>, PaletteLoaderError>, expected_line: usize) {
+        match result {
+            Err(PaletteLoaderError::Parse { line, .. }) => assert_eq!(line, expected_line)
+            , other => panic!("expected a parse error at line {expected_line}, got {other:?}")
+        }
+    }
+
+    #[test]
+    fn gpl_parses_colors_skipping_metadata_comments_and_blank_lines() {
+        let text = "GIMP Palette\nName: Test\nColumns: 2\n# comment\n\n  0   0   0\tBlack\n255 128 64 Orange\n";
+        let colors = parse_gpl(text).unwrap();
+        assert_eq!(colors, vec![Color::srgb_u8(0, 0, 0), Color::srgb_u8(255, 128, 64)]);
+    }
+
+    #[test]
+    fn gpl_rejects_missing_header_and_malformed_lines() {
+        assert_parse_error(parse_gpl("0 0 0\n"), 1);
+        assert_parse_error(parse_gpl("GIMP Palette\n# comment\n0 0\n"), 3);
+        assert_parse_error(parse_gpl("GIMP Palette\n0 0 256\n"), 2);
+    }
+
+    #[test]
+    fn hex_parses_colors_with_and_without_hash() {
+        let colors = parse_hex("000000\n\n  #ff8040  \n").unwrap();
+        assert_eq!(colors, vec![Color::srgb_u8(0, 0, 0), Color::srgb_u8(255, 128, 64)]);
+    }
+
+    #[test]
+    fn hex_rejects_malformed_lines() {
+        assert_parse_error(parse_hex("000000\n\nnot-a-color\n"), 3);
+    }
+
+    #[test]
+    fn jasc_pal_parses_the_declared_number_of_colors() {
+        let text = "JASC-PAL\n0100\n2\n0 0 0\n\n255 128 64\n";
+        let colors = parse_jasc_pal(text).unwrap();
+        assert_eq!(colors, vec![Color::srgb_u8(0, 0, 0), Color::srgb_u8(255, 128, 64)]);
+    }
+
+    #[test]
+    fn jasc_pal_rejects_malformed_files() {
+        assert_parse_error(parse_jasc_pal("GIMP Palette\n"), 1);
+        assert_parse_error(parse_jasc_pal("JASC-PAL\n"), 2);
+        assert_parse_error(parse_jasc_pal("JASC-PAL\n0100\nmany\n"), 3);
+        assert_parse_error(parse_jasc_pal("JASC-PAL\n0100\n3\n0 0 0\n255 255 255\n"), 3);
+        assert_parse_error(parse_jasc_pal("JASC-PAL\n0100\n2\n0 0 0\nwhite\n"), 5);
+    }
+
+    #[test]
+    fn palettes_over_the_limit_are_truncated() {
+        let colors = (0..MAX_PALETTE_COLORS + 10).map(|i| Color::srgb_u8(i as u8, 0, 0)).collect::<Vec<_>>();
+        assert_eq!(usable_palette_colors(&colors).len(), MAX_PALETTE_COLORS);
+        assert_eq!(usable_palette_colors(&colors[..2]).len(), 2);
+    }
+
+    #[test]
+    fn texture_data_has_a_linear_row_and_an_oklab_row() {
+        let colors = [Color::BLACK, Color::WHITE, Color::srgb_u8(255, 0, 0)];
+        // 2行 × 色数 × RGBA × f32
+        assert_eq!(palette_texture_data(&colors).len(), 2 * colors.len() * 4 * 4);
+    }
+}
@@ -0,0 +1,113 @@
+use bevy::{
+    prelude::*
+    , platform::collections::HashMap
+    , render::{
+        render_resource::*
+        , renderer::{RenderDevice, RenderQueue}
+        , view::ViewTarget
+    }
+};
+use crate::plugins::structs::post_effect::*;
+
+//
+// スタックの効果ごとのユニフォームを PostEffectUniforms に書き込み、ビューごとに動的オフセットを記録する
+//
+pub fn prepare_post_effect_uniforms(
+    mut commands: Commands
+    , render_device: Res<RenderDevice>
+    , render_queue: Res<RenderQueue>
+    , mut uniforms: ResMut<PostEffectUniforms>
+    , stacks: Query<(Entity, &ExtractedPostEffectStack)>
+) {
+    let alignment = render_device.limits().min_uniform_buffer_offset_alignment as usize;
+    let buffer = &mut uniforms.buffer;
+    buffer.clear();
+
+    for (entity, stack) in &stacks {
+        let offsets = stack.effects
+            .iter()
+            .map(|effect| {
+                let ExtractedPostEffect::Effect { uniform, .. } = effect else {
+                    // 段階は PostProcessSettings のユニフォームを使う
+                    return 0;
+                };
+                // 動的オフセットはアライメントの倍数にする必要があるので間を 0 で埋める
+                let offset = buffer.len().next_multiple_of(alignment);
+                buffer.values_mut().resize(offset, 0);
+                buffer.extend(uniform.iter().copied());
+                offset as u32
+            })
+            .collect();
+        commands.entity(entity).insert(ViewPostEffectOffsets { offsets });
+    }
+
+    buffer.write_buffer(&render_device, &render_queue);
+}
+
+//
+// ビューごとにスタックの効果のバインドグループを用意する
+// 入力のテクスチャ・ユニフォームのバッファ・効果の種類が前のフレームと同じ場合はキャッシュしたものを使い回す
+// 入力のテクスチャはノードの実行時まで決まらないので2枚のメインテクスチャの両方について用意する
+// ※ 追加のバインディングがある効果は、ワールドのリソースがいつ変わるか分からないので毎回作り直す
+//    追加のバインディングはワールドから取り出すので排他システムにしている
+//
+pub fn prepare_post_effect_bind_groups(world: &mut World) {
+    let mut views = world.query::<(Entity, &ViewTarget, &ExtractedPostEffectStack)>();
+    world.resource_scope(|world, mut cache: Mut<PostEffectBindGroupCache>| {
+        let mut previous = std::mem::take(&mut cache.views);
+        let Some(buffer) = world.resource::<PostEffectUniforms>().buffer.buffer() else {
+            return;
+        };
+        let render_device = world.resource::<RenderDevice>();
+        let post_effect_pipelines = world.resource::<PostEffectPipelines>();
+
+        let mut views_bind_groups = HashMap::default();
+        for (entity, view_target, stack) in views.iter(world) {
+            let mut view_cached = previous.remove(&entity).unwrap_or_default();
+            view_cached.resize_with(stack.effects.len(), Vec::new);
+
+            let bind_groups = stack.effects
+                .iter()
+                .zip(view_cached)
+                .map(|(effect, mut cached)| {
+                    // 段階はポストプロセスのバインドグループを使い、未登録の効果はノードで飛ばされるので用意しない
+                    let ExtractedPostEffect::Effect { effect_type, .. } = effect else {
+                        return Vec::new();
+                    };
+                    let Some(effect_pipeline) = post_effect_pipelines.pipelines.get(effect_type) else {
+                        return Vec::new();
+                    };
+                    [view_target.main_texture_view(), view_target.main_texture_other_view()]
+                        .into_iter()
+                        .map(|source| {
+                            let key = PostEffectBindGroupKey {
+                                source: source.id()
+                                , buffer: buffer.id()
+                                , effect_type: *effect_type
+                            };
+                            let reusable = cached.iter()
+                                .position(|cached| cached.key == key)
+                                .filter(|_| !effect_pipeline.has_extra_bindings);
+                            if let Some(index) = reusable {
+                                return cached.swap_remove(index);
+                            }
+
+                            let bind_group = effect_pipeline.pass_layout.bind_group(
+                                render_device
+                                , "post_effect_bind_group"
+                                , source
+                                , BindingResource::Buffer(BufferBinding { buffer, offset: 0, size: Some(effect_pipeline.uniform_size) })
+                                , (effect_pipeline.extra_bindings)(world)
+                            );
+                            CachedPostEffectBindGroup { key, bind_group }
+                        })
+                        .collect()
+                })
+                .collect();
+            views_bind_groups.insert(entity, bind_groups);
+        }
+
+        // このフレームにないビューのキャッシュは previous と一緒に破棄される
+        cache.views = views_bind_groups;
+    });
+}
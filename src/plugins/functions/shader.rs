@@ -1,17 +1,26 @@
 use bevy::{
     prelude::*
-    , core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state
-    , render::render_resource::*
+    , core_pipeline::tonemapping::Tonemapping
+    , render::{
+        render_resource::*
+        , render_asset::RenderAssets
+        , view::{ExtractedView, ViewTarget}
+    }
 };
+use crate::plugins::structs::components::{PostProcessSettings, PostProcessShaderOverride};
 use crate::plugins::structs::post_processes::*;
+use crate::plugins::structs::custom_post_process::*;
+use crate::plugins::structs::placement::PostProcessPlacement;
+use crate::plugins::structs::threshold_map::GpuThresholdMap;
+use crate::plugins::structs::post_effect::{ExtractedPostEffectStack, PostProcessStage};
 
 //
 // PostProcessShader の値を変更された際に変更されたシェーダーに切り替える
+// シェーダーは特殊化のキーに含まれるので、次の prepare_post_process_pipelines で新しいシェーダーのパイプラインが選ばれる
 //
 pub fn rebuild_pipeline_when_shader_changes(
     shader_resource: Res<PostProcessShader>
     , mut pipeline: ResMut<PostProcessPipeline>
-    , cache: ResMut<PipelineCache>,
 ) {
     // 変更がない場合は何もしない
     if !shader_resource.is_changed() { return; }
@@ -19,28 +28,75 @@ pub fn rebuild_pipeline_when_shader_changes(
     // シェーダーが同じなら何もしない
     if pipeline.shader_handle == shader_resource.0 { return; }
 
-    // 新しいシェーダーでパイプラインを再キュー
-    let new_id = cache.queue_render_pipeline(RenderPipelineDescriptor {
-        label: Some("post_process_pipeline".into())
-        , layout: vec![pipeline.layout.clone()]
-        , vertex: fullscreen_shader_vertex_state()
-        , fragment: Some(FragmentState {
-            shader: shader_resource.0.clone() // 新ハンドル
-            , shader_defs: vec![]
-            , entry_point: "fragment".into()
-            , targets: vec![Some(ColorTargetState {
-                format: TextureFormat::bevy_default()
-                , blend: None
-                , write_mask: ColorWrites::ALL
-            })]
-        })
-        , primitive: PrimitiveState::default()
-        , depth_stencil: None
-        , multisample: MultisampleState::default()
-        , push_constant_ranges: vec![]
-        , zero_initialize_workgroup_memory: false
-    });
+    pipeline.shader_handle = shader_resource.0.clone(); // 新ハンドル
+}
+
+//
+// ビューの設定とシェーダー、出力先のテクスチャの形式に合わせて特殊化したパイプラインを用意する
+// パイプラインはシェーダーごとにもキャッシュされる（PostProcessShaderOverride のカメラは別のパイプラインになる）
+// シェーダーが使えない場合に備えて入力をそのまま出力するパイプラインも用意する
+// PostEffectStack に積まれた段階は段階ごとにパイプラインを用意し、残りの段階を PostProcessNode のパイプラインで描く
+// HDR のカメラでもトーンマッピング後は 0.0～1.0 に収まっているので、
+// トーンマッピングの前に置いた場合とトーンマッピングしないカメラの場合のみ HDR の値として扱う
+//
+#[allow(clippy::type_complexity)]
+pub fn prepare_post_process_pipelines(
+    mut commands: Commands
+    , pipeline_cache: Res<PipelineCache>
+    , pipeline: Res<PostProcessPipeline>
+    , placement: Res<PostProcessPlacement>
+    , mut specialized_pipelines: ResMut<SpecializedRenderPipelines<PostProcessPipeline>>
+    , gpu_threshold_maps: Res<RenderAssets<GpuThresholdMap>>
+    , views: Query<(
+        Entity
+        , &ExtractedView
+        , &ViewTarget
+        , &PostProcessSettings
+        , Option<&PostProcessShaderOverride>
+        , Option<&Tonemapping>
+        , Option<&ExtractedPostEffectStack>
+    )>
+) {
+    for (entity, view, view_target, settings, shader_override, tonemapping, stack) in &views {
+        let hdr = view.hdr && (
+            placement.is_before_tonemapping()
+            || tonemapping.is_none_or(|tonemapping| *tonemapping == Tonemapping::None)
+        );
+        let shader = shader_override.map_or(&pipeline.shader_handle, |shader_override| &shader_override.0);
+        let threshold_map_ready = settings.threshold_map.as_ref()
+            .is_some_and(|threshold_map| gpu_threshold_maps.get(threshold_map).is_some());
+        let (stacked_stages, node_stages): (Vec<_>, Vec<_>) = PostProcessStage::ALL
+            .into_iter()
+            .partition(|stage| stack.is_some_and(|stack| stack.has_stage(*stage)));
+        let mut specialize = |stages: &[PostProcessStage]| {
+            let key = PostProcessPipelineKey::from_settings(shader.id(), settings, stages, threshold_map_ready, view_target.main_texture_format(), hdr);
+            specialized_pipelines.specialize(&pipeline_cache, &pipeline, key)
+        };
+        let id = (!node_stages.is_empty()).then(|| specialize(&node_stages));
+        let stage_ids = stacked_stages
+            .into_iter()
+            .map(|stage| (stage, specialize(&[stage])))
+            .collect();
+        let fallback_key = PostProcessPipelineKey::passthrough(pipeline.passthrough_shader.id(), view_target.main_texture_format());
+        let fallback_id = specialized_pipelines.specialize(&pipeline_cache, &pipeline, fallback_key);
+        commands.entity(entity).insert(ViewPostProcessPipeline { id, stage_ids, shader: shader.id(), fallback_id });
+    }
+}
+
+//
+// CustomPostProcessShader<T> の値を変更された際に変更されたシェーダーでパイプラインを作り直す
+//
+pub fn rebuild_custom_pipeline_when_shader_changes<T: CustomPostProcessSettings>(
+    shader_resource: Res<CustomPostProcessShader<T>>
+    , mut pipeline: ResMut<CustomPostProcessPipeline<T>>
+    , cache: Res<PipelineCache>
+) {
+    // 変更がない場合は何もしない
+    if !shader_resource.is_changed() { return; }
+
+    // シェーダーが同じなら何もしない
+    if pipeline.shader_handle == shader_resource.handle { return; }
 
-    pipeline.pipeline_id = new_id;
-    pipeline.shader_handle = shader_resource.0.clone();
+    pipeline.pipeline_ids = CustomPostProcessPipeline::<T>::queue_pipelines(&pipeline.pass_layout, &cache, shader_resource.handle.clone());
+    pipeline.shader_handle = shader_resource.handle.clone();
 }
@@ -0,0 +1,81 @@
+use bevy::{
+    prelude::*
+    , asset::LoadState
+    , render::render_resource::{CachedPipelineState, PipelineCache}
+};
+use crate::plugins::structs::components::PostProcessShaderOverride;
+use crate::plugins::structs::post_processes::{PostProcessShader, ViewPostProcessPipeline};
+use crate::plugins::structs::status::*;
+
+//
+// ビューごとのポストプロセスのパイプラインの作成状況をシェーダーごとにまとめてメインワールドに渡す
+// パイプラインの作成はこのフレームの描画前に処理されるので、その後の Cleanup で確認する
+//
+pub fn track_post_process_pipelines(
+    pipeline_cache: Res<PipelineCache>
+    , pipeline_status: Res<PostProcessPipelineStatus>
+    , views: Query<&ViewPostProcessPipeline>
+) {
+    // ポストプロセスを行うビューがない間は前の状態のままにする
+    if views.is_empty() {
+        return;
+    }
+
+    let mut statuses = PostProcessShaderStatuses::default();
+    for view_pipeline in &views {
+        for id in view_pipeline.ids() {
+            let status = match pipeline_cache.get_render_pipeline_state(id) {
+                CachedPipelineState::Ok(_) => PostProcessStatus::Ready
+                , CachedPipelineState::Err(error) => PostProcessStatus::Failed(error.to_string())
+                , _ => PostProcessStatus::Loading
+            };
+            statuses.merge(view_pipeline.shader, status);
+        }
+    }
+    pipeline_status.set(statuses);
+}
+
+//
+// シェーダーの読み込みとパイプラインの作成状況からシェーダーごとの状態と PostProcessStatus を更新する
+// 新しく失敗したシェーダーはログに出してイベントを送る
+// ※ 読み込みに失敗したシェーダーはパイプラインの作成待ちのままになるので読み込みの状態を先に見る
+//   カメラごとの PostProcessShaderOverride のシェーダーも同じように確認する
+//
+pub fn update_post_process_status(
+    asset_server: Res<AssetServer>
+    , shader: Res<PostProcessShader>
+    , overrides: Query<&PostProcessShaderOverride>
+    , pipeline_status: Res<PostProcessPipelineStatus>
+    , mut shader_statuses: ResMut<PostProcessShaderStatuses>
+    , mut status: ResMut<PostProcessStatus>
+    , mut failures: EventWriter<PostProcessFailed>
+) {
+    let pipeline_statuses = pipeline_status.get();
+    let mut next = PostProcessShaderStatuses::default();
+    for handle in std::iter::once(&shader.0).chain(overrides.iter().map(|shader_override| &shader_override.0)) {
+        let shader_status = match asset_server.load_state(handle) {
+            LoadState::Failed(error) => PostProcessStatus::Failed(format!("could not load post process shader: {error}"))
+            , _ => pipeline_statuses.get(handle.id()).cloned().unwrap_or_default()
+        };
+        next.merge(handle.id(), shader_status);
+    }
+    if *shader_statuses == next {
+        return;
+    }
+
+    for (shader, shader_status) in &next.0 {
+        let PostProcessStatus::Failed(error) = shader_status else {
+            continue;
+        };
+        if !shader_statuses.is_failed(*shader) {
+            error!("post process shader {shader:?} falls back to passthrough: {error}");
+            failures.write(PostProcessFailed { shader: *shader, error: error.clone() });
+        }
+    }
+
+    let summary = next.summary();
+    if *status != summary {
+        *status = summary;
+    }
+    *shader_statuses = next;
+}
@@ -0,0 +1,117 @@
+use bevy::{
+    prelude::*
+    , core_pipeline::prepass::{DepthPrepass, NormalPrepass}
+    , render::view::{ExtractedView, ViewTarget}
+};
+use crate::plugins::structs::components::{EdgePrepasses, PostProcessSettings, PostProcessUniform};
+
+//
+// 深度・法線でエッジを検出するカメラにそれぞれのプリパスを追加する
+// ※ マルチサンプルのプリパスのテクスチャはシェーダーで扱っていないため MSAA も無効にする（無効にした時は警告を出す）
+// ※ 2D のカメラにはプリパスがないので追加しない（深度・法線のエッジは検出されない）
+// 追加したものは EdgePrepasses に記録し、深度・法線を使わなくなった時やポストプロセスを外した時に取り除いて MSAA を元に戻す
+//
+#[allow(clippy::type_complexity)]
+pub fn require_edge_prepasses(
+    mut commands: Commands
+    , cameras: Query<
+        (Entity, &PostProcessSettings, Option<&EdgePrepasses>, Has<DepthPrepass>, Has<NormalPrepass>, Option<&Msaa>)
+        , (With<Camera3d>, Changed<PostProcessSettings>)
+    >
+    , mut removed: RemovedComponents<PostProcessSettings>
+    , added: Query<&EdgePrepasses, Without<PostProcessSettings>>
+) {
+    for (entity, settings, edge_prepasses, has_depth_prepass, has_normal_prepass, msaa) in &cameras {
+        let mut state = edge_prepasses.cloned().unwrap_or_default();
+        let mut entity_commands = commands.entity(entity);
+
+        match (settings.edges.depth.is_some(), state.depth) {
+            (true, false) if !has_depth_prepass => {
+                entity_commands.insert(DepthPrepass);
+                state.depth = true;
+            }
+            (false, true) => {
+                entity_commands.remove::<DepthPrepass>();
+                state.depth = false;
+            }
+            _ => {}
+        }
+        match (settings.edges.normal.is_some(), state.normal) {
+            (true, false) if !has_normal_prepass => {
+                entity_commands.insert(NormalPrepass);
+                state.normal = true;
+            }
+            (false, true) => {
+                entity_commands.remove::<NormalPrepass>();
+                state.normal = false;
+            }
+            _ => {}
+        }
+
+        // カメラに元から付いていたプリパスでも、深度・法線のエッジ検出に使う間は MSAA を無効にする
+        let needs_msaa_off = settings.edges.depth.is_some() || settings.edges.normal.is_some();
+        match (needs_msaa_off, state.previous_msaa) {
+            (true, None) => {
+                let msaa = msaa.copied().unwrap_or_default();
+                if msaa != Msaa::Off {
+                    warn!("{entity}: MSAA ({msaa:?}) is disabled because depth/normal edge detection does not support multisampled prepasses");
+                    entity_commands.insert(Msaa::Off);
+                    state.previous_msaa = Some(msaa);
+                }
+            }
+            (false, Some(previous_msaa)) => {
+                entity_commands.insert(previous_msaa);
+                state.previous_msaa = None;
+            }
+            _ => {}
+        }
+
+        if state == EdgePrepasses::default() {
+            if edge_prepasses.is_some() {
+                entity_commands.remove::<EdgePrepasses>();
+            }
+        } else if edge_prepasses != Some(&state) {
+            entity_commands.insert(state);
+        }
+    }
+
+    // ポストプロセスを外したカメラは追加したものをすべて取り除く
+    for entity in removed.read() {
+        let Ok(state) = added.get(entity) else { continue };
+        let mut entity_commands = commands.entity(entity);
+        if state.depth {
+            entity_commands.remove::<DepthPrepass>();
+        }
+        if state.normal {
+            entity_commands.remove::<NormalPrepass>();
+        }
+        if let Some(previous_msaa) = state.previous_msaa {
+            entity_commands.insert(previous_msaa);
+        }
+        entity_commands.remove::<EdgePrepasses>();
+    }
+}
+
+//
+// ビューごとに決まる値をユニフォームに設定する
+// 描画サイズ: ビューのテクスチャのサイズ（ウィンドウのサイズやサブカメラの変更に追従する）
+// 射影: 深度の線形化に必要な値 x: clip_from_view[2][2], y: clip_from_view[3][2], z: 透視投影なら 1.0
+//
+pub fn prepare_post_process_view_uniforms(
+    mut views: Query<(&ExtractedView, &ViewTarget, &mut PostProcessUniform)>
+) {
+    for (view, view_target, mut uniform) in &mut views {
+        let size = view_target.main_texture().size();
+        uniform.screen_width = size.width as f32;
+        uniform.screen_height = size.height as f32;
+
+        let clip_from_view = view.clip_from_view;
+        let is_perspective = clip_from_view.w_axis.w == 0.0;
+        uniform.projection = Vec4::new(
+            clip_from_view.z_axis.z
+            , clip_from_view.w_axis.z
+            , if is_perspective { 1.0 } else { 0.0 }
+            , 0.0
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use std::{any::TypeId, marker::PhantomData};
+use bevy::{
+    prelude::*
+    , render::{
+        render_resource::{binding_types::uniform_buffer, *}
+        , renderer::RenderDevice
+        , RenderApp
+    }
+};
+use crate::plugins::structs::fullscreen::{FullscreenPassLayout, ViewFormatPipelineIds};
+use crate::plugins::structs::post_effect::*;
+
+//
+// ポストエフェクトの種類を登録するプラグイン
+// 効果の型ごとにパイプラインを作り、PostEffectStack に積まれた時に描画できるようにする
+//
+// app.add_plugins(PostEffectPlugin::<Grading>::default());
+//
+pub struct PostEffectPlugin<E: PostEffect>(PhantomData<E>);
+
+impl<E: PostEffect> Default for PostEffectPlugin<E> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<E: PostEffect> Plugin for PostEffectPlugin<E> {
+    fn build(&self, _app: &mut App) {}
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<PostEffectPipelines>();
+
+        let world = render_app.world_mut();
+        let shader = match E::shader() {
+            ShaderRef::Handle(handle) => handle
+            , ShaderRef::Path(path) => world.load_asset(path)
+            , ShaderRef::Default => {
+                error!("post effect {} has no shader", std::any::type_name::<E>());
+                return;
+            }
+        };
+
+        let extra_layout_entries = E::extra_layout_entries();
+        let has_extra_bindings = !extra_layout_entries.is_empty();
+        let pass_layout = FullscreenPassLayout::new(
+            world.resource::<RenderDevice>()
+            , "post_effect_bind_group_layout"
+            , uniform_buffer::<E::Uniform>(true)
+            , extra_layout_entries
+        );
+        let pipeline_ids = ViewFormatPipelineIds::queue(world.resource::<PipelineCache>(), |format| {
+            pass_layout.descriptor(format!("post_effect_pipeline_{}", std::any::type_name::<E>()), shader.clone(), format)
+        });
+
+        world.resource_mut::<PostEffectPipelines>().pipelines.insert(
+            TypeId::of::<E>()
+            , PostEffectPipeline {
+                pass_layout
+                , pipeline_ids
+                , uniform_size: E::Uniform::min_size()
+                , has_extra_bindings
+                , extra_bindings: E::extra_bindings
+            }
+        );
+    }
+}
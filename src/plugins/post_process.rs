@@ -1,7 +1,11 @@
 use std::borrow::Cow;
 use bevy::{
     prelude::*
-    , core_pipeline::{core_3d::graph::{Core3d, Node3d}}
+    , diagnostic::{Diagnostic, RegisterDiagnostic}
+    , core_pipeline::{
+        core_2d::graph::Core2d
+        , core_3d::graph::Core3d
+    }
     , render::{
         extract_component::{
             ExtractComponentPlugin
@@ -9,24 +13,57 @@ use bevy::{
         }
         , extract_resource::ExtractResourcePlugin
         , render_graph::{
-            RenderGraphApp
+            RenderGraph
+            , RenderGraphApp
+            , RenderLabel
             , ViewNodeRunner
         }
+        , render_asset::RenderAssetPlugin
+        , render_resource::{SpecializedMeshPipelines, SpecializedRenderPipelines}
+        , ExtractSchedule
         , RenderApp
+        , RenderSet
     }
 };
 use crate::consts::app::*;
-use crate::plugins::structs::components::PostProcessSettings;
+use crate::plugins::structs::components::{PostProcessPalette, PostProcessSettings, PostProcessShaderOverride, PostProcessUniform};
 use crate::plugins::structs::post_processes::*;
-use crate::plugins::functions::shader::rebuild_pipeline_when_shader_changes;
+use crate::plugins::structs::palette::*;
+use crate::plugins::structs::threshold_map::*;
+use crate::plugins::functions::shader::*;
+use crate::plugins::functions::view::*;
+use crate::plugins::structs::outline::*;
+use crate::plugins::functions::outline::*;
+use crate::plugins::structs::post_effect::*;
+use crate::plugins::structs::grading::Grading;
+use crate::plugins::functions::post_effect::{prepare_post_effect_bind_groups, prepare_post_effect_uniforms};
+use crate::plugins::post_effect::PostEffectPlugin;
+use crate::plugins::structs::placement::PostProcessPlacement;
+use crate::plugins::functions::graph::add_placement_edges;
+use crate::plugins::structs::bind_group_cache::*;
+use crate::plugins::functions::bind_group_cache::*;
+use crate::plugins::structs::status::*;
+use crate::plugins::functions::status::*;
+use crate::plugins::functions::blue_noise::finish_blue_noise_texture;
+#[cfg(not(feature = "webgl2"))]
+use {
+    crate::plugins::structs::error_diffusion::*
+    , crate::plugins::functions::error_diffusion::prepare_error_diffusion_buffers
+};
 
 #[derive(Resource, Clone)]
 pub struct PostProcessDefaults {
     pub shader_path: Cow<'static, str>
+    , pub blue_noise_size: u32 // ブルーノイズテクスチャの一辺のサイズ
+    , pub blue_noise_seed: u64 // ブルーノイズ生成の乱数シード（同じ値なら同じテクスチャになる）
 }
 impl Default for PostProcessDefaults {
     fn default() -> Self {
-        Self { shader_path: Cow::Borrowed(DEFAULT_SHADER_PATH) }
+        Self {
+            shader_path: Cow::Borrowed(DEFAULT_SHADER_PATH)
+            , blue_noise_size: DEFAULT_BLUE_NOISE_SIZE
+            , blue_noise_seed: DEFAULT_BLUE_NOISE_SEED
+        }
     }
 }
 
@@ -35,42 +72,187 @@ impl Default for PostProcessDefaults {
 // 参考
 // https://bevy.org/examples/shaders/custom-post-processing/
 //
-pub struct PostProcessPlugin;
+// app.add_plugins(PostProcessPlugin { placement: PostProcessPlacement::AfterUi });
+//
+#[derive(Default)]
+pub struct PostProcessPlugin {
+    pub placement: PostProcessPlacement // レンダーグラフ上のノードの位置
+}
 impl Plugin for PostProcessPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<PostProcessDefaults>();
         app.init_resource::<PostProcessShader>();
+        app.init_resource::<PostProcessPassthroughShader>();
+        app.init_resource::<PostProcessStatus>();
+        app.init_resource::<PostProcessShaderStatuses>();
+        app.add_event::<PostProcessFailed>();
+        app.init_resource::<BlueNoiseTexture>();
+        app.init_resource::<BayerTexture>();
+        app.init_asset::<Palette>();
+        app.init_asset_loader::<PaletteLoader>();
+        app.init_asset::<ThresholdMap>();
+        app.init_asset_loader::<ThresholdMapLoader>();
         app.add_plugins((
             ExtractComponentPlugin::<PostProcessSettings>::default()
-            , UniformComponentPlugin::<PostProcessSettings>::default()
+            , UniformComponentPlugin::<PostProcessUniform>::default()
+            , ExtractComponentPlugin::<PostProcessPalette>::default()
+            , ExtractComponentPlugin::<PostProcessShaderOverride>::default()
+            , ExtractComponentPlugin::<PostEffectStack>::default()
+            , PostEffectPlugin::<Grading>::default()
+            , RenderAssetPlugin::<GpuPalette>::default()
+            , RenderAssetPlugin::<GpuThresholdMap>::default()
             , ExtractResourcePlugin::<PostProcessShader>::default()
+            , ExtractResourcePlugin::<BlueNoiseTexture>::default()
+            , ExtractResourcePlugin::<BayerTexture>::default()
+            , ExtractResourcePlugin::<PostProcessShaderStatuses>::default()
         ));
+        app.add_systems(Update, (require_edge_prepasses, require_outline_depth_textures, propagate_outlines, measure_post_process_bind_groups, update_post_process_status, finish_blue_noise_texture));
+
+        // レンダーワールドで作成したバインドグループの数を診断として記録する
+        let bind_group_counter = PostProcessBindGroupCounter::default();
+        app.insert_resource(bind_group_counter.clone());
+        app.register_diagnostic(Diagnostic::new(POST_PROCESS_BIND_GROUPS_CREATED));
+
+        // レンダーワールドで確認したパイプラインの状態を PostProcessStatus に反映する
+        let pipeline_status = PostProcessPipelineStatus::default();
+        app.insert_resource(pipeline_status.clone());
 
         let shader = app.world().resource::<PostProcessShader>().clone();
+        let passthrough_shader = app.world().resource::<PostProcessPassthroughShader>().clone();
         // We need to get the render app from the main app
         if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
             render_app.insert_resource(shader);
+            render_app.insert_resource(passthrough_shader);
+            render_app.insert_resource(pipeline_status);
+            render_app.insert_resource(self.placement);
+            render_app.insert_resource(bind_group_counter);
+            render_app.init_resource::<PostProcessBindGroupCache>();
+            render_app.init_resource::<SpecializedRenderPipelines<PostProcessPipeline>>();
+            render_app.init_resource::<SpecializedMeshPipelines<OutlineMaskPipeline>>();
+            render_app.init_resource::<ExtractedOutlines>();
+            render_app.init_resource::<OutlineBuffers>();
+            render_app.init_resource::<PostEffectUniforms>();
+            render_app.init_resource::<PostEffectBindGroupCache>();
 
+            // ノードの前後の辺は他のプラグインのノードが揃ってから finish で placement に合わせて張る
             render_app
                 .add_render_graph_node::<ViewNodeRunner<PostProcessNode>>(
                     Core3d
                     , PostProcessLabel
                 )
+                .add_systems(
+                        bevy::render::Render
+                        , (
+                            (
+                                rebuild_pipeline_when_shader_changes
+                                , prepare_post_process_pipelines
+                            ).chain().in_set(RenderSet::Prepare)
+                            , prepare_post_process_view_uniforms.in_set(RenderSet::Queue)
+                            , prepare_post_process_bind_groups.in_set(RenderSet::PrepareBindGroups)
+                            , track_post_process_pipelines.in_set(RenderSet::Cleanup)
+                        )
+                );
+
+            // ポストエフェクトのスタックはポストプロセスの直前に描く
+            // スタックに積まれていないディザ・エッジの段階はスタックの後のポストプロセスのノードで描く
+            render_app
+                .add_render_graph_node::<ViewNodeRunner<PostEffectStackNode>>(
+                    Core3d
+                    , PostEffectStackLabel
+                )
                 .add_render_graph_edges(
                     Core3d,
                     (
-                        Node3d::Tonemapping
+                        PostEffectStackLabel
                         , PostProcessLabel
-                        , Node3d::EndMainPassPostProcessing
                     )
                     ,
                 )
                 .add_systems(
                         bevy::render::Render
-                        , rebuild_pipeline_when_shader_changes
+                        , (
+                            prepare_post_effect_uniforms.in_set(RenderSet::PrepareResources)
+                            , prepare_post_effect_bind_groups.in_set(RenderSet::PrepareBindGroups)
+                        )
+                );
+
+            // Outlined のメッシュのアウトラインはディザの影響を受けないようにポストプロセスの後に描く
+            render_app
+                .add_render_graph_node::<ViewNodeRunner<OutlineNode>>(
+                    Core3d
+                    , OutlineLabel
+                )
+                .add_render_graph_edges(
+                    Core3d,
+                    (
+                        PostProcessLabel
+                        , OutlineLabel
+                    )
+                    ,
+                )
+                .add_systems(ExtractSchedule, extract_outlines)
+                .add_systems(
+                        bevy::render::Render
+                        , (prepare_outline_objects, prepare_outline_views)
+                            .chain()
+                            .in_set(RenderSet::PrepareResources)
+                );
+
+            // 誤差拡散はコンピュートシェーダーを使うため WebGL2 では登録しない
+            #[cfg(not(feature = "webgl2"))]
+            render_app
+                .add_render_graph_node::<ViewNodeRunner<ErrorDiffusionNode>>(
+                    Core3d
+                    , ErrorDiffusionLabel
+                )
+                .add_render_graph_edges(
+                    Core3d,
+                    (
+                        PostProcessLabel
+                        , ErrorDiffusionLabel
+                        , OutlineLabel
+                    )
+                    ,
+                )
+                .add_systems(
+                        bevy::render::Render
+                        , prepare_error_diffusion_buffers.in_set(RenderSet::PrepareResources)
+                );
+
+            // 2D のカメラにも同じ PostProcessSettings でディザとエッジを適用する
+            // Outlined は 3D のメッシュのみが対象なのでアウトラインのノードは登録しない
+            render_app
+                .add_render_graph_node::<ViewNodeRunner<PostEffectStackNode>>(
+                    Core2d
+                    , PostEffectStackLabel
+                )
+                .add_render_graph_node::<ViewNodeRunner<PostProcessNode>>(
+                    Core2d
+                    , PostProcessLabel
+                )
+                .add_render_graph_edges(
+                    Core2d,
+                    (
+                        PostEffectStackLabel
+                        , PostProcessLabel
+                    )
+                    ,
+                );
+
+            #[cfg(not(feature = "webgl2"))]
+            render_app
+                .add_render_graph_node::<ViewNodeRunner<ErrorDiffusionNode>>(
+                    Core2d
+                    , ErrorDiffusionLabel
+                )
+                .add_render_graph_edges(
+                    Core2d,
+                    (
+                        PostProcessLabel
+                        , ErrorDiffusionLabel
+                    )
+                    ,
                 );
-        } else {
-            return;
         }
     }
 
@@ -80,5 +262,24 @@ impl Plugin for PostProcessPlugin {
         };
 
         render_app.init_resource::<PostProcessPipeline>();
+        render_app.init_resource::<PostEffectPipelines>();
+        render_app.init_resource::<OutlineMaskPipeline>();
+        render_app.init_resource::<OutlinePipeline>();
+        render_app.init_resource::<JumpFloodPipeline>();
+        #[cfg(not(feature = "webgl2"))]
+        render_app.init_resource::<ErrorDiffusionPipeline>();
+
+        // ポストプロセスのノードの列を placement の位置に繋ぐ
+        // 2D はアウトラインのノードがない以外は 3D と同じ
+        #[cfg(not(feature = "webgl2"))]
+        let chain_2d = vec![PostEffectStackLabel.intern(), PostProcessLabel.intern(), ErrorDiffusionLabel.intern()];
+        #[cfg(feature = "webgl2")]
+        let chain_2d = vec![PostEffectStackLabel.intern(), PostProcessLabel.intern()];
+        let mut chain_3d = chain_2d.clone();
+        chain_3d.push(OutlineLabel.intern());
+
+        let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
+        add_placement_edges(&mut render_graph, Core3d, &chain_3d, self.placement, PostProcessPlacement::anchors_3d);
+        add_placement_edges(&mut render_graph, Core2d, &chain_2d, self.placement, PostProcessPlacement::anchors_2d);
     }
 }
@@ -1,2 +1,14 @@
 pub mod post_processes;
-pub mod components;
\ No newline at end of file
+pub mod components;
+pub mod settings;
+pub mod status;
+pub mod error_diffusion;
+pub mod palette;
+pub mod threshold_map;
+pub mod outline;
+pub mod fullscreen;
+pub mod post_effect;
+pub mod grading;
+pub mod custom_post_process;
+pub mod placement;
+pub mod bind_group_cache;
\ No newline at end of file
@@ -0,0 +1,73 @@
+use std::sync::{
+    atomic::{AtomicU32, Ordering}
+    , Arc
+};
+use bevy::{
+    prelude::*
+    , diagnostic::DiagnosticPath
+    , platform::collections::HashMap
+    , render::render_resource::*
+};
+
+//
+// フレームごとに作成したポストプロセスのバインドグループの数の診断
+//
+pub const POST_PROCESS_BIND_GROUPS_CREATED: DiagnosticPath = DiagnosticPath::const_new("post_process/bind_groups_created");
+
+//
+// バインドグループにバインドしたリソースの ID
+// どれかが変わった場合のみバインドグループを作り直す
+//
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PostProcessBindGroupKey {
+    pub source: TextureViewId          // 入力の画面のテクスチャ（ViewTarget の2枚のメインテクスチャのどちらか）
+    , pub settings: BufferId           // 設定のユニフォームのバッファ
+    , pub blue_noise: TextureViewId
+    , pub palette: TextureViewId
+    , pub bayer: TextureViewId
+    , pub threshold_map: TextureViewId
+    , pub depth: TextureViewId
+    , pub normal: TextureViewId
+}
+
+pub struct CachedPostProcessBindGroup {
+    pub key: PostProcessBindGroupKey
+    , pub bind_group: BindGroup
+}
+
+//
+// ビューごとにキャッシュしたポストプロセスのバインドグループ
+// 入力になりうる2枚のメインテクスチャのそれぞれについて用意する
+//
+#[derive(Resource, Default)]
+pub struct PostProcessBindGroupCache {
+    pub views: HashMap<Entity, Vec<CachedPostProcessBindGroup>>
+}
+
+impl PostProcessBindGroupCache {
+    // ビューの入力のテクスチャに対応するバインドグループ
+    pub fn get(&self, view: Entity, source: &TextureView) -> Option<&BindGroup> {
+        self.views
+            .get(&view)?
+            .iter()
+            .find(|cached| cached.key.source == source.id())
+            .map(|cached| &cached.bind_group)
+    }
+}
+
+//
+// レンダーワールドで作成したバインドグループの数をメインワールドの診断に渡すためのカウンター
+// 同じ値をメインワールドとレンダーワールドの両方のリソースとして持つ
+//
+#[derive(Resource, Clone, Default)]
+pub struct PostProcessBindGroupCounter(Arc<AtomicU32>);
+
+impl PostProcessBindGroupCounter {
+    pub fn set(&self, count: u32) {
+        self.0.store(count, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -1,89 +1,495 @@
 /*
   ポストプロセス設定構造体
   ※ こちらに修正を加えたらシェーダー側に定義している構造体も同じように修正を加えること
+     （レイアウトが一致しているかは下の tests で確認できる）
 */
 use bevy::{
     prelude::*,
-    render::{
-        extract_component::ExtractComponent,
-        render_resource::*,
-    },
+    render::extract_component::ExtractComponent,
 };
 use crate::consts::app::*;
+use crate::plugins::structs::palette::Palette;
+use crate::plugins::structs::settings::*;
+use crate::plugins::structs::threshold_map::ThresholdMap;
 
-#[derive(Clone, Copy, ShaderType)]
-pub struct DitherSettings {
-    pub is_enable:        u32 // ディザを適用するかどうか 1=ON 0=OFF
-    , pub is_monochrome:  u32 // モノクロディザにするかどうか 1=ON 0=OFF
-    , pub intensity:      f32 // グレースケール閾値
-    , pub scale:          i32 // ディザのスケール
-    , pub weight_scaling: f32 // 閾値ごとにかけるディザ（ベイヤー行列）を決めるための係数、数値が大きいほどグレーの濃淡の識別が増えるがベイヤー行列の種類数に合わせないと意味がないので注意
-    , pub _pad_0:         f32
-    , pub _pad_1:         f32
-    , pub _pad_2:         f32
+//
+// GPU に渡す構造体
+// ShaderType の derive は構造体の外側に型検査用の関数（check）を生成し、その関数が未使用（dead_code）と判定される
+// 構造体に付けた allow はその関数には届かないため、ShaderType の構造体だけをこのモジュールにまとめて抑制する
+//
+#[allow(dead_code)]
+mod uniforms {
+    use bevy::{prelude::*, render::render_resource::ShaderType};
+
+    #[derive(Clone, Copy, ShaderType)]
+    pub(crate) struct DitherUniform {
+        pub is_enable:        u32 // ディザを適用するかどうか 1=ON 0=OFF
+        , pub is_monochrome:  u32 // モノクロディザにするかどうか 1=ON 0=OFF
+        , pub intensity:      f32 // グレースケール閾値
+        , pub scale:          i32 // ディザのスケール
+        , pub weight_scaling: f32 // 閾値ごとにかけるディザ（ベイヤー行列）を決めるための係数
+        , pub mode:           u32 // 閾値マップの種類 DITHER_MODE_*
+        , pub diffusion_kernel: u32 // 誤差拡散のカーネル DIFFUSION_KERNEL_*
+        , pub serpentine:     u32 // 誤差拡散を蛇行走査にするかどうか 1=ON 0=OFF
+        , pub bayer_levels:   u32 // 使うベイヤー行列のサイズ BAYER_* の組み合わせ
+        , pub _pad_0:         u32
+        , pub _pad_1:         u32
+        , pub _pad_2:         u32
+    }
+
+    #[derive(Clone, Copy, ShaderType)]
+    pub(crate) struct EdgeUniform {
+        pub is_enable:       u32  // エッジを適用するかどうか 1=ON 0=OFF
+        , pub edge_strength: f32  // エッジ強度の検出閾値
+        , pub luminance_enable: u32 // 輝度の差でエッジを検出するかどうか 1=ON 0=OFF
+        , pub depth_enable:  u32  // 深度の差でエッジを検出するかどうか 1=ON 0=OFF
+        , pub depth_threshold: f32 // 深度エッジの検出閾値
+        , pub normal_enable: u32  // 法線の角度差でエッジを検出するかどうか 1=ON 0=OFF
+        , pub normal_threshold: f32 // 法線エッジの検出閾値（度数法）
+        , pub kernel:        u32  // 輝度エッジの検出カーネル（EDGE_KERNEL_*、パイプラインの特殊化に使われシェーダーからは参照しない）
+        , pub non_max_suppression: u32 // 非極大値抑制でエッジを細線化するかどうか 1=ON 0=OFF（同上）
+        , pub thickness:     f32  // エッジの太さ（ピクセル）
+        , pub blend_mode:    u32  // エッジの色の合成方法（EDGE_BLEND_*）
+        , pub color:         Vec4 // エッジの色（線形 RGB、a は合成の強さ）
+    }
+
+    #[derive(Clone, Copy, ShaderType)]
+    pub(crate) struct HalftoneUniform {
+        pub is_enable:     u32  // ハーフトーンを適用するかどうか 1=ON 0=OFF
+        , pub is_cmyk:     u32  // CMYK の4版にするかどうか 1=ON 0=OFF（単色）
+        , pub dot_shape:   u32  // 網点の形 HALFTONE_DOT_*
+        , pub cell_size:   f32  // 網点1つ分のセルの大きさ（ピクセル）
+        , pub angle:       f32  // 単色の場合のスクリーン角度（度）
+        , pub _pad_0:      f32
+        , pub _pad_1:      f32
+        , pub _pad_2:      f32
+        , pub cmyk_angles: Vec4 // CMYK それぞれのスクリーン角度（度）
+    }
+
+    //
+    // シェーダーに渡すポストプロセスの設定（シェーダー側の PostProcessSettings 構造体）
+    //
+    #[derive(Component, Clone, Copy, ShaderType)]
+    pub struct PostProcessUniform {
+        pub is_enable:       u32
+        , pub screen_width:  f32 // 描画幅（レンダーワールドでビューのテクスチャのサイズが設定される）
+        , pub screen_height: f32 // 描画高さ（同上）
+        , pub(crate) _pad_0: f32
+        , pub(crate) dither: DitherUniform
+        , pub(crate) edge: EdgeUniform
+        , pub(crate) halftone: HalftoneUniform
+        , pub projection: Vec4 // 深度の線形化に使う射影行列の値（レンダーワールドでビューごとに設定される）
+        ,
+        #[cfg(feature = "webgl2")]
+        pub _webgl2_padding: Vec3,
+    }
+
+    //
+    // 誤差拡散パスのコンピュートシェーダーに渡すパラメータ
+    // ※ こちらも error_diffusion.wgsl 側の構造体と同じ並びにすること
+    //
+    #[derive(Clone, Copy, Default, ShaderType)]
+    pub struct ErrorDiffusionParams {
+        pub width:        u32 // 処理する画像の幅
+        , pub height:     u32 // 処理する画像の高さ
+        , pub kernel:     u32 // 拡散カーネル DIFFUSION_KERNEL_*
+        , pub serpentine: u32 // 蛇行走査 1=ON 0=OFF
+        , pub monochrome: u32 // 白黒の2値にするかどうか 1=ON 0=OFF
+        , pub _pad_0:     u32
+        , pub _pad_1:     u32
+        , pub _pad_2:     u32
+    }
+
+    //
+    // 誤差拡散の1回のディスパッチで処理する行の範囲（動的オフセットでディスパッチごとに切り替える）
+    // ※ error_diffusion.wgsl 側の構造体と同じ並びにすること
+    //
+    #[derive(Clone, Copy, Default, PartialEq, Debug, ShaderType)]
+    pub struct ErrorDiffusionBand {
+        pub first_row:   u32 // 最初の行
+        , pub row_count: u32 // 行数（ERROR_DIFFUSION_WORKGROUP_ROWS 以下）
+        , pub _pad_0:    u32
+        , pub _pad_1:    u32
+    }
 }
+pub use uniforms::{PostProcessUniform, ErrorDiffusionParams, ErrorDiffusionBand};
+pub(crate) use uniforms::{DitherUniform, EdgeUniform, HalftoneUniform};
 
-impl Default for DitherSettings {
-    fn default() -> Self {
+impl From<&Dither> for DitherUniform {
+    fn from(dither: &Dither) -> Self {
+        let (diffusion_kernel, serpentine) = match dither.mode {
+            DitherMode::ErrorDiffusion { kernel, serpentine } => (kernel, serpentine)
+            , _ => (DiffusionKernel::default(), DEFAULT_DIFFUSION_SERPENTINE)
+        };
+        Self {
+            is_enable:        dither.enabled as u32
+            , is_monochrome:  dither.monochrome as u32
+            , intensity:      dither.intensity
+            , scale:          dither.scale
+            , weight_scaling: dither.weight_scaling
+            , mode:           dither.mode.raw()
+            , diffusion_kernel: diffusion_kernel.raw()
+            , serpentine:     serpentine as u32
+            , bayer_levels:   dither.bayer_levels.raw()
+            , _pad_0: 0
+            , _pad_1: 0
+            , _pad_2: 0
+        }
+    }
+}
+
+impl From<&Edges> for EdgeUniform {
+    fn from(edges: &Edges) -> Self {
         Self {
-            is_enable:        DEFAULT_DITHER_ENABLE
-            , is_monochrome:  DEFAULT_DITHER_MONOCHROME
-            , intensity:      DEFAULT_DITHER_INTENSITY
-            , scale:          DEFAULT_DITHER_SCALE
-            , weight_scaling: DEFAULT_WEIGHT_SCALE
+            is_enable:       edges.enabled as u32
+            , edge_strength: edges.strength
+            , luminance_enable: edges.luminance as u32
+            , depth_enable:  edges.depth.is_some() as u32
+            , depth_threshold: edges.depth.unwrap_or_default()
+            , normal_enable: edges.normal.is_some() as u32
+            , normal_threshold: edges.normal.unwrap_or_default()
+            , kernel:        edges.kernel.raw()
+            , non_max_suppression: edges.non_max_suppression as u32
+            , thickness:     edges.thickness
+            , blend_mode:    edges.blend_mode.raw()
+            , color:         edges.color.to_linear().to_vec4()
+        }
+    }
+}
+
+impl From<&Halftone> for HalftoneUniform {
+    fn from(halftone: &Halftone) -> Self {
+        Self {
+            is_enable:     halftone.enabled as u32
+            , is_cmyk:     halftone.cmyk as u32
+            , dot_shape:   halftone.dot.raw()
+            , cell_size:   halftone.cell_size
+            , angle:       halftone.angle
             , _pad_0: 0.0
             , _pad_1: 0.0
             , _pad_2: 0.0
+            , cmyk_angles: halftone.cmyk_angles
         }
     }
 }
 
-#[derive(Clone, Copy, ShaderType)]
-pub struct EdgeSettings {
-    pub is_enable:       u32  // エッジを適用するかどうか 1=ON 0=OFF
-    , pub edge_strength: f32  // エッジ強度の検出閾値
-    ,
-    #[cfg(feature = "webgl2")]
-    pub _edge_padding: Vec2
+//
+// カメラに付けるポストプロセスの設定
+// PostProcessSettings::builder() で値を確認しながら組み立てる（Default は確認済みの既定値）
+// GPU に渡す際は PostProcessUniform に変換される
+// ON/OFF の値とディザ・エッジ検出の種類はピクセルごとに分岐させず、パイプラインの特殊化のキー（PostProcessPipelineKey）になる
+//
+#[derive(Component, Clone)]
+pub struct PostProcessSettings {
+    pub(crate) enabled: bool
+    , pub(crate) dither: Dither
+    , pub(crate) edges: Edges
+    , pub(crate) halftone: Halftone
+    , pub(crate) threshold_map: Option<Handle<ThresholdMap>> // ベイヤー行列の代わりに使う閾値マップ（None の場合はベイヤー行列）
 }
 
-impl Default for EdgeSettings {
+impl Default for PostProcessSettings {
     fn default() -> Self {
         Self {
-            is_enable:       DEFAULT_EDGE_ENABLE
-            , edge_strength: DEFAULT_EDGE_STRENGTH
-            ,
-            #[cfg(feature = "webgl2")]
-            _edge_padding: Vec2::ZERO
+            enabled: DEFAULT_POSTPROCESS_ENABLE
+            , dither: Dither::default()
+            , edges: Edges::default()
+            , halftone: Halftone::default()
+            , threshold_map: None
         }
     }
 }
 
-#[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
-pub struct PostProcessSettings {
-    pub is_enable:       u32 // ポストプロセスを適用するかどうか 1=ON 0=OFF
-    , pub screen_width:  f32 // 描画幅
-    , pub screen_height: f32 // 描画高さ
-    , _pad_0:            f32
-    , dither: DitherSettings
-    , edge: EdgeSettings
-    ,
-    #[cfg(feature = "webgl2")]
-    pub _webgl2_padding: Vec3,
+impl PostProcessSettings {
+    pub fn builder() -> PostProcessSettingsBuilder {
+        PostProcessSettingsBuilder::default()
+    }
+
+    // 今の設定から一部だけ変えた設定を作る場合に使う
+    pub fn to_builder(&self) -> PostProcessSettingsBuilder {
+        PostProcessSettingsBuilder::from_settings(self.clone())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn dither(&self) -> &Dither {
+        &self.dither
+    }
+
+    pub fn edges(&self) -> &Edges {
+        &self.edges
+    }
+
+    pub fn halftone(&self) -> &Halftone {
+        &self.halftone
+    }
+
+    pub fn threshold_map(&self) -> Option<&Handle<ThresholdMap>> {
+        self.threshold_map.as_ref()
+    }
+
+    // 誤差拡散のディザを使う場合の拡散カーネルと蛇行走査の有無
+    pub(crate) fn error_diffusion(&self) -> Option<(DiffusionKernel, bool)> {
+        match self.dither.mode {
+            DitherMode::ErrorDiffusion { kernel, serpentine } if self.enabled && self.dither.enabled => Some((kernel, serpentine))
+            , _ => None
+        }
+    }
 }
 
-impl Default for PostProcessSettings {
-    fn default() -> Self {
+// レンダーワールドへは設定そのものと GPU 用のユニフォームの両方を渡す
+impl ExtractComponent for PostProcessSettings {
+    type QueryData = &'static Self;
+    type QueryFilter = ();
+    type Out = (Self, PostProcessUniform);
+
+    fn extract_component(settings: &Self) -> Option<Self::Out> {
+        Some((settings.clone(), PostProcessUniform::from(settings)))
+    }
+}
+
+impl From<&PostProcessSettings> for PostProcessUniform {
+    fn from(settings: &PostProcessSettings) -> Self {
         Self {
-            is_enable: DEFAULT_POSTPROCESS_ENABLE
-            , screen_width: GAME_WIDTH
-            , screen_height: GAME_HEIGHT
+            is_enable: settings.enabled as u32
+            , screen_width: 0.0
+            , screen_height: 0.0
             , _pad_0: 0.0
-            , dither: DitherSettings::default()
-            , edge: EdgeSettings::default()
+            , dither: DitherUniform::from(&settings.dither)
+            , edge: EdgeUniform::from(&settings.edges)
+            , halftone: HalftoneUniform::from(&settings.halftone)
+            , projection: Vec4::ZERO
             ,
             #[cfg(feature = "webgl2")]
             _webgl2_padding: Vec3::ZERO,
         }
     }
 }
+
+//
+// カメラごとに指定する減色パレット
+// PostProcessSettings と同じカメラに付けると、カラーディザが白黒ではなくパレット内の近い2色の間でディザをかける
+// 誤差拡散のディザの場合はパレット内の最も近い色に量子化して誤差を拡散する
+// 色の近さは OKLab 空間で判定する（最大 MAX_PALETTE_COLORS 色、2色未満の場合は使われない）
+// パレットはファイルから読み込むか Assets<Palette> に追加したものを指定する
+//
+#[derive(Component, Clone, Default, ExtractComponent)]
+pub struct PostProcessPalette(pub Handle<Palette>);
+
+//
+// カメラごとに PostProcessShader の代わりに使うシェーダー
+// ミニマップ用のカメラだけ別の見た目にする場合などに使う
+// バインディングは post_process.wgsl と同じ並びにすること（シェーダー定義も同じものが渡される）
+//
+#[derive(Component, Clone, ExtractComponent)]
+pub struct PostProcessShaderOverride(pub Handle<Shader>);
+
+//
+// require_edge_prepasses がカメラに追加したプリパスと、無効にする前の MSAA の設定
+// エッジ検出で深度・法線を使わなくなった時に、ここに記録したものだけを取り除いて元に戻す
+//
+#[derive(Component, Clone, Default, PartialEq, Debug)]
+pub struct EdgePrepasses {
+    pub depth: bool              // DepthPrepass を追加したかどうか
+    , pub normal: bool           // NormalPrepass を追加したかどうか
+    , pub previous_msaa: Option<Msaa> // Msaa::Off にする前の設定（MSAA を変更していない場合は None）
+}
+
+//
+// Rust 側のユニフォームの構造体と post_process.wgsl の構造体のメンバーのオフセットとサイズが一致するかの確認
+// GPU は使わず、naga_oil でシェーダー定義を展開してから naga でレイアウトを計算する
+//
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use naga_oil::compose::{ComposableModuleDescriptor, Composer, NagaModuleDescriptor, ShaderDefValue};
+    use super::*;
+    use bevy::render::render_resource::{
+        encase::{self, internal::WriteInto}
+        , ShaderType
+    };
+
+    // メンバーごとの（オフセット, サイズ）と構造体全体のサイズ
+    #[derive(PartialEq, Debug)]
+    struct Layout {
+        members: Vec<(u64, u64)>
+        , size: u64
+    }
+
+    // SIXTEEN_BYTE_ALIGNMENT を定義するかどうかを指定してシェーダーを読み込む
+    fn shader_module(sixteen_byte_alignment: bool) -> naga::Module {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets").join(DEFAULT_SHADER_PATH);
+        let source = std::fs::read_to_string(&path).expect("post process shader could not be read");
+
+        // インポートしている Bevy のモジュールは頂点シェーダーの出力の型だけなので同じ定義で代用する
+        let mut composer = Composer::default();
+        composer.add_composable_module(ComposableModuleDescriptor {
+            source: "#define_import_path bevy_core_pipeline::fullscreen_vertex_shader\n\
+                     struct FullscreenVertexOutput { @builtin(position) position: vec4<f32>, @location(0) uv: vec2<f32> };\n"
+            , file_path: "fullscreen_vertex_shader.wgsl"
+            , ..default()
+        }).expect("fullscreen vertex shader module could not be added");
+
+        let shader_defs = [("SIXTEEN_BYTE_ALIGNMENT".to_string(), ShaderDefValue::Bool(true))]
+            .into_iter()
+            .filter(|_| sixteen_byte_alignment)
+            .collect::<HashMap<_, _>>();
+        composer.make_naga_module(NagaModuleDescriptor {
+            source: &source
+            , file_path: &path.to_string_lossy()
+            , shader_defs
+            , ..default()
+        }).unwrap_or_else(|error| panic!("{}", error.emit_to_string(&composer)))
+    }
+
+    // シェーダー側の構造体のレイアウト
+    fn wgsl_layout(module: &naga::Module, name: &str) -> Layout {
+        let mut layouter = naga::proc::Layouter::default();
+        layouter.update(module.to_ctx()).expect("shader layout could not be computed");
+
+        let (_, ty) = module.types
+            .iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some(name))
+            .unwrap_or_else(|| panic!("struct {name} is not defined in the shader"));
+        let naga::TypeInner::Struct { members, span } = &ty.inner else {
+            panic!("{name} is not a struct");
+        };
+        Layout {
+            members: members
+                .iter()
+                .map(|member| (member.offset as u64, layouter[member.ty].size as u64))
+                .collect()
+            , size: *span as u64
+        }
+    }
+
+    // すべてのバイトが 0 の値と、すべてのバイトが 0 でない値を作れる型
+    trait Marked {
+        fn zeroed() -> Self;
+        fn marked() -> Self;
+    }
+
+    impl Marked for u32 {
+        fn zeroed() -> Self { 0 }
+        fn marked() -> Self { u32::MAX }
+    }
+
+    impl Marked for i32 {
+        fn zeroed() -> Self { 0 }
+        fn marked() -> Self { -1 }
+    }
+
+    impl Marked for f32 {
+        fn zeroed() -> Self { 0.0 }
+        fn marked() -> Self { f32::from_bits(u32::MAX) }
+    }
+
+    impl Marked for Vec3 {
+        fn zeroed() -> Self { Vec3::ZERO }
+        fn marked() -> Self { Vec3::splat(f32::marked()) }
+    }
+
+    impl Marked for Vec4 {
+        fn zeroed() -> Self { Vec4::ZERO }
+        fn marked() -> Self { Vec4::splat(f32::marked()) }
+    }
+
+    // メンバーを1つずつ marked() にする関数の一覧（宣言の順）
+    trait UniformMembers: Marked + Sized {
+        fn members() -> Vec<fn(&mut Self)>;
+    }
+
+    // ユニフォームの構造体に Marked と UniformMembers を実装する（メンバーは宣言の順に並べること）
+    macro_rules! uniform_members {
+        ($ty:ty { $($(#[$attr:meta])* $field:ident),* $(,)? }) => {
+            impl Marked for $ty {
+                fn zeroed() -> Self {
+                    Self { $($(#[$attr])* $field: Marked::zeroed()),* }
+                }
+                fn marked() -> Self {
+                    Self { $($(#[$attr])* $field: Marked::marked()),* }
+                }
+            }
+
+            impl UniformMembers for $ty {
+                fn members() -> Vec<fn(&mut Self)> {
+                    vec![$($(#[$attr])* |value: &mut Self| value.$field = Marked::marked()),*]
+                }
+            }
+        };
+    }
+
+    uniform_members!(DitherUniform {
+        is_enable, is_monochrome, intensity, scale, weight_scaling, mode, diffusion_kernel, serpentine, bayer_levels
+        , _pad_0, _pad_1, _pad_2
+    });
+    uniform_members!(EdgeUniform {
+        is_enable, edge_strength, luminance_enable, depth_enable, depth_threshold, normal_enable, normal_threshold
+        , kernel, non_max_suppression, thickness, blend_mode, color
+    });
+    uniform_members!(HalftoneUniform {
+        is_enable, is_cmyk, dot_shape, cell_size, angle, _pad_0, _pad_1, _pad_2, cmyk_angles
+    });
+    uniform_members!(PostProcessUniform {
+        is_enable, screen_width, screen_height, _pad_0, dither, edge, halftone, projection
+        , #[cfg(feature = "webgl2")] _webgl2_padding
+    });
+
+    // Rust 側の構造体のレイアウト
+    // ShaderType の書き込みの結果から求める（1つのメンバーだけを marked() にして書き込み、0 でなくなったバイトの範囲をそのメンバーとする）
+    fn rust_layout<T: ShaderType + WriteInto + UniformMembers>() -> Layout {
+        let bytes = |value: &T| {
+            let mut buffer = encase::UniformBuffer::new(Vec::<u8>::new());
+            buffer.write(value).expect("uniform could not be written");
+            buffer.into_inner()
+        };
+        Layout {
+            members: T::members()
+                .into_iter()
+                .map(|mark| {
+                    let mut value = T::zeroed();
+                    mark(&mut value);
+                    let bytes = bytes(&value);
+                    let start = bytes.iter().position(|byte| *byte != 0).expect("member was not written");
+                    let end = bytes.iter().rposition(|byte| *byte != 0).expect("member was not written") + 1;
+                    (start as u64, (end - start) as u64)
+                })
+                .collect()
+            , size: T::min_size().get()
+        }
+    }
+
+    #[test]
+    fn nested_settings_match_shader_in_both_configurations() {
+        for sixteen_byte_alignment in [false, true] {
+            let module = shader_module(sixteen_byte_alignment);
+            assert_eq!(rust_layout::<DitherUniform>(), wgsl_layout(&module, "DitherSettings"), "DitherSettings (SIXTEEN_BYTE_ALIGNMENT: {sixteen_byte_alignment})");
+            assert_eq!(rust_layout::<EdgeUniform>(), wgsl_layout(&module, "EdgeSettings"), "EdgeSettings (SIXTEEN_BYTE_ALIGNMENT: {sixteen_byte_alignment})");
+            assert_eq!(rust_layout::<HalftoneUniform>(), wgsl_layout(&module, "HalftoneSettings"), "HalftoneSettings (SIXTEEN_BYTE_ALIGNMENT: {sixteen_byte_alignment})");
+        }
+    }
+
+    // webgl2 の feature と SIXTEEN_BYTE_ALIGNMENT は対応していなければならない（Bevy は WebGL2 で後者を定義する）
+    // ※ どちらの構成も確認するため、CI では --features webgl2 を付けた場合と付けない場合の両方でテストを実行すること
+    #[test]
+    fn post_process_uniform_matches_shader() {
+        let webgl2 = cfg!(feature = "webgl2");
+        assert_eq!(
+            rust_layout::<PostProcessUniform>()
+            , wgsl_layout(&shader_module(webgl2), "PostProcessSettings")
+            , "PostProcessSettings (SIXTEEN_BYTE_ALIGNMENT: {webgl2})"
+        );
+    }
+
+    // 逆の組み合わせではパディングの分だけずれること（確認が両方の設定を区別できていること）
+    #[test]
+    fn post_process_uniform_differs_from_other_configuration() {
+        let webgl2 = cfg!(feature = "webgl2");
+        assert_ne!(
+            rust_layout::<PostProcessUniform>()
+            , wgsl_layout(&shader_module(!webgl2), "PostProcessSettings")
+        );
+    }
+}
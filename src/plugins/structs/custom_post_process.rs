@@ -0,0 +1,161 @@
+use std::marker::PhantomData;
+use bevy::{
+    prelude::*
+    , ecs::query::QueryItem
+    , render::{
+        extract_component::{ComponentUniforms, DynamicUniformIndex}
+        , extract_resource::ExtractResource
+        , render_graph::{
+            NodeRunError
+            , RenderGraphContext
+            , RenderLabel
+            , ViewNode
+        }
+        , render_resource::{
+            binding_types::uniform_buffer
+            , encase::internal::WriteInto
+            , *
+        }
+        , renderer::{RenderContext, RenderDevice}
+        , view::ViewTarget
+    }
+};
+use crate::plugins::structs::fullscreen::*;
+
+//
+// CustomPostProcessPlugin::<T> の設定のコンポーネントが満たすべきトレイト
+// T はそのままレンダーワールドに抽出され、ユニフォームとしてシェーダーに渡される
+//
+pub trait CustomPostProcessSettings: Component + ShaderType + WriteInto + Clone {}
+impl<T: Component + ShaderType + WriteInto + Clone> CustomPostProcessSettings for T {}
+
+//
+// T のポストプロセスで使うシェーダーを持つリソース
+// 値を差し替えるとパイプラインが作り直される
+//
+#[derive(Resource)]
+pub struct CustomPostProcessShader<T> {
+    pub handle: Handle<Shader>
+    , marker: PhantomData<fn() -> T>
+}
+
+impl<T> CustomPostProcessShader<T> {
+    pub fn new(handle: Handle<Shader>) -> Self {
+        Self { handle, marker: PhantomData }
+    }
+}
+
+impl<T> Clone for CustomPostProcessShader<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.handle.clone())
+    }
+}
+
+impl<T: CustomPostProcessSettings> ExtractResource for CustomPostProcessShader<T> {
+    type Source = Self;
+
+    fn extract_resource(source: &Self::Source) -> Self {
+        source.clone()
+    }
+}
+
+//
+// T のポストプロセスのパイプラインを保持するリソース
+// FullscreenPassLayout の並びで、ユニフォームには T の値が入る（追加のバインディングはなし）
+//   @group(0) @binding(0) var screen_texture: texture_2d<f32>;
+//   @group(0) @binding(1) var texture_sampler: sampler;
+//   @group(0) @binding(2) var<uniform> settings: T;
+//
+#[derive(Resource)]
+pub struct CustomPostProcessPipeline<T> {
+    pub pass_layout: FullscreenPassLayout
+    , pub pipeline_ids: ViewFormatPipelineIds
+    , pub shader_handle: Handle<Shader>
+    , marker: PhantomData<fn() -> T>
+}
+
+impl<T: CustomPostProcessSettings> CustomPostProcessPipeline<T> {
+    // シェーダーから出力先の形式ごとのパイプラインをキューに入れる
+    pub fn queue_pipelines(
+        pass_layout: &FullscreenPassLayout
+        , pipeline_cache: &PipelineCache
+        , shader: Handle<Shader>
+    ) -> ViewFormatPipelineIds {
+        ViewFormatPipelineIds::queue(pipeline_cache, |format| {
+            pass_layout.descriptor(format!("custom_post_process_pipeline_{}", std::any::type_name::<T>()), shader.clone(), format)
+        })
+    }
+}
+
+impl<T: CustomPostProcessSettings> FromWorld for CustomPostProcessPipeline<T> {
+    fn from_world(world: &mut World) -> Self {
+        let pass_layout = FullscreenPassLayout::new(
+            world.resource::<RenderDevice>()
+            , "custom_post_process_bind_group_layout"
+            , uniform_buffer::<T>(true)
+            , Vec::new()
+        );
+        let shader_handle = world.resource::<CustomPostProcessShader<T>>().handle.clone();
+        let pipeline_ids = Self::queue_pipelines(&pass_layout, world.resource::<PipelineCache>(), shader_handle.clone());
+        Self {
+            pass_layout
+            , pipeline_ids
+            , shader_handle
+            , marker: PhantomData
+        }
+    }
+}
+
+//
+// CustomPostProcessPlugin のデフォルトのラベル（設定の型名で区別する）
+//
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct CustomPostProcessLabel(pub &'static str);
+
+//
+// T のポストプロセスのレンダーパイプラインノードの定義
+//
+pub struct CustomPostProcessNode<T>(PhantomData<fn() -> T>);
+
+impl<T> Default for CustomPostProcessNode<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: CustomPostProcessSettings> ViewNode for CustomPostProcessNode<T> {
+    type ViewQuery = (
+        &'static ViewTarget
+        , &'static DynamicUniformIndex<T>
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, settings_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let custom_pipeline = world.resource::<CustomPostProcessPipeline<T>>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(custom_pipeline.pipeline_ids.get(view_target)) else {
+            return Ok(());
+        };
+        let settings_uniforms = world.resource::<ComponentUniforms<T>>();
+        let Some(settings_binding) = settings_uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+        let bind_group = custom_pipeline.pass_layout.bind_group(
+            render_context.render_device()
+            , "custom_post_process_bind_group"
+            , post_process.source
+            , settings_binding
+            , Vec::new()
+        );
+        draw_fullscreen_pass(render_context, "custom_post_process_pass", post_process.destination, pipeline, &bind_group, &[settings_index.index()]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+use bevy::{
+    prelude::*
+    , core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state
+    , ecs::query::QueryItem
+    , render::{
+        render_graph::{
+            NodeRunError
+            , RenderGraphContext
+            , RenderLabel
+            , ViewNode
+        }
+        , render_resource::{
+            binding_types::{storage_buffer_read_only_sized, storage_buffer_sized, texture_2d, uniform_buffer}
+            , *
+        }
+        , renderer::{RenderContext, RenderDevice}
+        , view::ViewTarget
+    }
+};
+
+use crate::consts::app::*;
+use crate::plugins::structs::components::{ErrorDiffusionBand, ErrorDiffusionParams, PostProcessSettings};
+use crate::plugins::structs::fullscreen::ViewFormatPipelineIds;
+
+//
+// 誤差拡散のパイプラインを保持するリソース
+// コンピュートで誤差を拡散するパイプラインと、結果を画面に描画するパイプラインの2つを持つ
+//
+#[derive(Resource)]
+pub struct ErrorDiffusionPipeline {
+    pub diffuse_layout: BindGroupLayout
+    , pub blit_layout: BindGroupLayout
+    , pub diffuse_pipeline_id: CachedComputePipelineId
+    , pub blit_pipeline_ids: ViewFormatPipelineIds // ビューの出力先の形式ごと
+}
+impl FromWorld for ErrorDiffusionPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let diffuse_layout = render_device.create_bind_group_layout(
+            "error_diffusion_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::COMPUTE,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: false })
+                    , storage_buffer_sized(false, None)
+                    , uniform_buffer::<ErrorDiffusionParams>(false)
+                    , texture_2d(TextureSampleType::Float { filterable: false })
+                    , uniform_buffer::<ErrorDiffusionBand>(true)
+                ),
+            )
+        );
+        let blit_layout = render_device.create_bind_group_layout(
+            "error_diffusion_blit_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    storage_buffer_read_only_sized(false, None)
+                    , uniform_buffer::<ErrorDiffusionParams>(false)
+                ),
+            )
+        );
+
+        let shader = world.load_asset(ERROR_DIFFUSION_SHADER_PATH);
+        let cache = world.resource::<PipelineCache>();
+        let diffuse_pipeline_id = cache.queue_compute_pipeline(ComputePipelineDescriptor {
+            label: Some("error_diffusion_pipeline".into())
+            , layout: vec![diffuse_layout.clone()]
+            , push_constant_ranges: vec![]
+            , shader: shader.clone()
+            , shader_defs: vec![]
+            , entry_point: "diffuse".into()
+            , zero_initialize_workgroup_memory: false
+        });
+        let blit_pipeline_ids = ViewFormatPipelineIds::queue(cache, |format| RenderPipelineDescriptor {
+            label: Some("error_diffusion_blit_pipeline".into())
+            , layout: vec![blit_layout.clone()]
+            , vertex: fullscreen_shader_vertex_state()
+            , fragment: Some(FragmentState {
+                shader: shader.clone()
+                , shader_defs: vec![]
+                , entry_point: "blit".into()
+                , targets: vec![Some(ColorTargetState {
+                    format
+                    , blend: None
+                    , write_mask: ColorWrites::ALL
+                })]
+            })
+            , primitive: PrimitiveState::default()
+            , depth_stencil: None
+            , multisample: MultisampleState::default()
+            , push_constant_ranges: vec![]
+            , zero_initialize_workgroup_memory: false
+        });
+
+        Self {
+            diffuse_layout
+            , blit_layout
+            , diffuse_pipeline_id
+            , blit_pipeline_ids
+        }
+    }
+}
+
+//
+// ビューごとの誤差拡散用バッファ
+// 画面サイズが変わった時だけ作り直す
+//
+#[derive(Component)]
+pub struct ErrorDiffusionBuffers {
+    pub pixels: Buffer                             // 誤差の蓄積と量子化結果を持つバッファ（1ピクセル vec4<f32>）
+    , pub params: UniformBuffer<ErrorDiffusionParams>
+    , pub bands: DynamicUniformBuffer<ErrorDiffusionBand> // ディスパッチごとに処理する行の範囲
+    , pub band_offsets: Vec<u32>                   // bands のバンドごとの動的オフセット（上の行から順）
+    , pub size: UVec2
+    , pub bind_groups: Vec<CachedErrorDiffusionBindGroups> // 入力になりうる2枚のメインテクスチャのそれぞれについて用意する
+}
+
+impl ErrorDiffusionBuffers {
+    // ビューの入力のテクスチャに対応するバインドグループ
+    pub fn bind_groups(&self, source: &TextureView) -> Option<&CachedErrorDiffusionBindGroups> {
+        self.bind_groups.iter().find(|cached| cached.key.source == source.id())
+    }
+}
+
+//
+// 誤差拡散のバインドグループにバインドしたリソースの ID
+// どれかが変わった場合のみバインドグループを作り直す
+//
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ErrorDiffusionBindGroupKey {
+    pub source: TextureViewId // 入力の画面のテクスチャ（ViewTarget の2枚のメインテクスチャのどちらか）
+    , pub pixels: BufferId
+    , pub params: BufferId
+    , pub palette: TextureViewId
+    , pub bands: BufferId
+}
+
+pub struct CachedErrorDiffusionBindGroups {
+    pub key: ErrorDiffusionBindGroupKey
+    , pub diffuse: BindGroup
+    , pub blit: BindGroup
+}
+
+//
+// 誤差拡散を識別するためのラベル
+//
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct ErrorDiffusionLabel;
+
+//
+// 誤差拡散のレンダーパイプラインノードの定義
+// ディザの種類が誤差拡散のビューだけ処理する
+// 誤差の拡散は行のバンドごとに1回ずつディスパッチする（前のディスパッチの書き込みは次のディスパッチから見える）
+//
+#[derive(Default)]
+pub struct ErrorDiffusionNode;
+impl ViewNode for ErrorDiffusionNode {
+    type ViewQuery = (
+        &'static ViewTarget
+        , &'static PostProcessSettings
+        , &'static ErrorDiffusionBuffers
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, post_process_settings, buffers): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        if post_process_settings.error_diffusion().is_none() {
+            return Ok(());
+        }
+
+        let error_diffusion_pipeline = world.resource::<ErrorDiffusionPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let (Some(diffuse_pipeline), Some(blit_pipeline)) = (
+            pipeline_cache.get_compute_pipeline(error_diffusion_pipeline.diffuse_pipeline_id)
+            , pipeline_cache.get_render_pipeline(error_diffusion_pipeline.blit_pipeline_ids.get(view_target))
+        ) else {
+            return Ok(());
+        };
+        // バインドグループは prepare_error_diffusion_buffers で入力のテクスチャごとに用意済み
+        // post_process_write は入力と出力を入れ替えるので、使うバインドグループが見つかってから呼ぶ
+        let Some(bind_groups) = buffers.bind_groups(view_target.main_texture_view()) else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+
+        // 前フレームの誤差が残らないように毎回ゼロで初期化する
+        render_context.command_encoder().clear_buffer(&buffers.pixels, 0, None);
+        {
+            let mut compute_pass = render_context.command_encoder().begin_compute_pass(&ComputePassDescriptor {
+                label: Some("error_diffusion_pass")
+                , timestamp_writes: None
+            });
+            compute_pass.set_pipeline(diffuse_pipeline);
+            for &offset in &buffers.band_offsets {
+                compute_pass.set_bind_group(0, &bind_groups.diffuse, &[offset]);
+                compute_pass.dispatch_workgroups(1, 1, 1);
+            }
+        }
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("error_diffusion_blit_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination
+                , resolve_target: None
+                , ops: Operations::default()
+            })]
+            , depth_stencil_attachment: None
+            , timestamp_writes: None
+            , occlusion_query_set: None
+        });
+
+        render_pass.set_render_pipeline(blit_pipeline);
+        render_pass.set_bind_group(0, &bind_groups.blit, &[]);
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use std::borrow::Cow;
+use bevy::{
+    prelude::*
+    , core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state
+    , render::{
+        render_resource::{
+            binding_types::{sampler, texture_2d}
+            , *
+        }
+        , renderer::{RenderContext, RenderDevice}
+        , view::ViewTarget
+    }
+};
+
+//
+// 前のパスの結果を入力にして全画面を1枚のフラグメントシェーダーで描画するパスのレイアウトとサンプラー
+// ポストエフェクトのスタックの効果と CustomPostProcessPlugin のパスで共通に使う
+//
+// シェーダーのバインディングは以下の順に並ぶ
+//   @group(0) @binding(0) 前のパスの結果（texture_2d<f32>）
+//   @group(0) @binding(1) サンプラー
+//   @group(0) @binding(2) ユニフォーム（動的オフセット）
+//   @group(0) @binding(3) 以降 追加のバインディング
+//
+pub struct FullscreenPassLayout {
+    pub layout: BindGroupLayout
+    , pub sampler: Sampler
+}
+
+impl FullscreenPassLayout {
+    pub fn new(
+        render_device: &RenderDevice
+        , label: &'static str
+        , uniform: BindGroupLayoutEntryBuilder
+        , extra_entries: Vec<BindGroupLayoutEntryBuilder>
+    ) -> Self {
+        let entries = [
+            texture_2d(TextureSampleType::Float { filterable: true })
+            , sampler(SamplerBindingType::Filtering)
+            , uniform
+        ]
+            .into_iter()
+            .chain(extra_entries)
+            .enumerate()
+            .map(|(binding, entry)| entry.build(binding as u32, ShaderStages::FRAGMENT))
+            .collect::<Vec<_>>();
+
+        Self {
+            layout: render_device.create_bind_group_layout(label, &entries)
+            , sampler: render_device.create_sampler(&SamplerDescriptor::default())
+        }
+    }
+
+    // シェーダーと出力先の形式からパイプラインの設定を作る（エントリーポイントは "fragment"）
+    pub fn descriptor(&self, label: impl Into<Cow<'static, str>>, shader: Handle<Shader>, format: TextureFormat) -> RenderPipelineDescriptor {
+        RenderPipelineDescriptor {
+            label: Some(label.into())
+            , layout: vec![self.layout.clone()]
+            , vertex: fullscreen_shader_vertex_state()
+            , fragment: Some(FragmentState {
+                shader
+                , shader_defs: vec![]
+                , entry_point: "fragment".into()
+                , targets: vec![Some(ColorTargetState {
+                    format
+                    , blend: None
+                    , write_mask: ColorWrites::ALL
+                })]
+            })
+            , primitive: PrimitiveState::default()
+            , depth_stencil: None
+            , multisample: MultisampleState::default()
+            , push_constant_ranges: vec![]
+            , zero_initialize_workgroup_memory: false
+        }
+    }
+
+    // 入力のテクスチャ、ユニフォーム、追加のバインディングからバインドグループを作る
+    pub fn bind_group(
+        &self
+        , render_device: &RenderDevice
+        , label: &'static str
+        , source: &TextureView
+        , uniform: BindingResource
+        , extra_bindings: Vec<BindingResource>
+    ) -> BindGroup {
+        let entries = [
+            BindingResource::TextureView(source)
+            , BindingResource::Sampler(&self.sampler)
+            , uniform
+        ]
+            .into_iter()
+            .chain(extra_bindings)
+            .enumerate()
+            .map(|(binding, resource)| BindGroupEntry { binding: binding as u32, resource })
+            .collect::<Vec<_>>();
+        render_device.create_bind_group(label, &self.layout, &entries)
+    }
+}
+
+//
+// ビューの出力先の形式ごとのパイプラインの ID
+// HDR のカメラ（Rgba16Float）とそれ以外（bevy_default）の両方を作っておき、描画時にビューの形式で選ぶ
+//
+#[derive(Clone, Copy)]
+pub struct ViewFormatPipelineIds([CachedRenderPipelineId; 2]);
+impl ViewFormatPipelineIds {
+    // 出力先の形式ごとにパイプラインをキューに入れる
+    pub fn queue(
+        pipeline_cache: &PipelineCache
+        , descriptor: impl Fn(TextureFormat) -> RenderPipelineDescriptor
+    ) -> Self {
+        Self([TextureFormat::bevy_default(), ViewTarget::TEXTURE_FORMAT_HDR]
+            .map(|format| pipeline_cache.queue_render_pipeline(descriptor(format))))
+    }
+
+    // ビューの出力先の形式に合うパイプラインの ID
+    pub fn get(&self, view_target: &ViewTarget) -> CachedRenderPipelineId {
+        self.0[(view_target.main_texture_format() == ViewTarget::TEXTURE_FORMAT_HDR) as usize]
+    }
+}
+
+//
+// 全画面の三角形を1枚描画する
+//
+pub fn draw_fullscreen_pass(
+    render_context: &mut RenderContext
+    , label: &'static str
+    , destination: &TextureView
+    , pipeline: &RenderPipeline
+    , bind_group: &BindGroup
+    , offsets: &[u32]
+) {
+    let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: destination
+            , resolve_target: None
+            , ops: Operations::default()
+        })]
+        , depth_stencil_attachment: None
+        , timestamp_writes: None
+        , occlusion_query_set: None
+    });
+    render_pass.set_render_pipeline(pipeline);
+    render_pass.set_bind_group(0, bind_group, offsets);
+    render_pass.draw(0..3, 0..1);
+}
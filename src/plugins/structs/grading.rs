@@ -0,0 +1,70 @@
+use bevy::{
+    prelude::*
+    , render::render_resource::ShaderRef
+};
+
+use crate::consts::app::*;
+use crate::plugins::structs::post_effect::PostEffect;
+
+//
+// 色調補正のポストエフェクト
+// PostEffectStack に積んで使う
+//
+#[derive(Clone, Copy, Debug)]
+pub struct Grading {
+    pub exposure: f32     // 露出（段、0.0 で変化なし）
+    , pub contrast: f32   // コントラスト（1.0 で変化なし）
+    , pub saturation: f32 // 彩度（1.0 で変化なし、0.0 でグレースケール）
+    , pub tint: Color     // 乗算する色（白で変化なし）
+}
+
+impl Default for Grading {
+    fn default() -> Self {
+        Self {
+            exposure: DEFAULT_GRADING_EXPOSURE
+            , contrast: DEFAULT_GRADING_CONTRAST
+            , saturation: DEFAULT_GRADING_SATURATION
+            , tint: Color::WHITE
+        }
+    }
+}
+
+//
+// GPU に渡す構造体（dead_code を抑制する理由は components.rs の uniforms と同じ）
+//
+#[allow(dead_code)]
+mod uniforms {
+    use bevy::{prelude::*, render::render_resource::ShaderType};
+
+    //
+    // 色調補正のユニフォーム
+    // ※ grading.wgsl の Grading と同じ並びにすること
+    //
+    #[derive(Clone, Copy, ShaderType)]
+    pub struct GradingUniform {
+        pub exposure: f32
+        , pub contrast: f32
+        , pub saturation: f32
+        , pub _pad_0: f32
+        , pub tint: Vec4 // 線形 RGB
+    }
+}
+pub use uniforms::GradingUniform;
+
+impl PostEffect for Grading {
+    type Uniform = GradingUniform;
+
+    fn shader() -> ShaderRef {
+        GRADING_SHADER_PATH.into()
+    }
+
+    fn uniform(&self) -> GradingUniform {
+        GradingUniform {
+            exposure: self.exposure
+            , contrast: self.contrast
+            , saturation: self.saturation
+            , _pad_0: 0.0
+            , tint: self.tint.to_linear().to_vec4()
+        }
+    }
+}
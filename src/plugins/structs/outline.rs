@@ -0,0 +1,670 @@
+use bevy::{
+    prelude::*
+    , core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state
+    , ecs::query::QueryItem
+    , render::{
+        mesh::{allocator::MeshAllocator, MeshVertexBufferLayoutRef, RenderMesh, RenderMeshBufferInfo}
+        , render_asset::RenderAssets
+        , render_graph::{
+            NodeRunError
+            , RenderGraphContext
+            , RenderLabel
+            , ViewNode
+        }
+        , render_resource::{
+            binding_types::{sampler, texture_2d, texture_depth_2d, texture_depth_2d_multisampled, uniform_buffer}
+            , *
+        }
+        , renderer::{RenderContext, RenderDevice}
+        , texture::CachedTexture
+        , view::{ViewDepthTexture, ViewTarget}
+    }
+};
+
+use crate::consts::app::*;
+use crate::plugins::structs::fullscreen::{draw_fullscreen_pass, ViewFormatPipelineIds};
+
+//
+// アウトラインを描画するオブジェクトに付けるコンポーネント
+// SceneRoot に付けた場合は子孫のメッシュすべてに同じ設定が伝播される
+// ※ マスクは頂点の位置だけで描画するため、スキニングとモーフターゲットのアニメーションには追従しない
+//
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct Outlined {
+    pub color: Color  // アウトラインの色（アルファは合成の強さ）
+    , pub width: f32  // アウトラインの太さ（ピクセル、周囲を探索する描き方では MAX_OUTLINE_WIDTH まで）
+}
+
+impl Default for Outlined {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE
+            , width: 2.0
+        }
+    }
+}
+
+//
+// 親から伝播された Outlined であることを示すマーカー
+// 親の Outlined が取り除かれた時に一緒に取り除く対象を判別するために使う
+//
+#[derive(Component, Clone, Copy, Default)]
+pub struct PropagatedOutline;
+
+//
+// require_outline_depth_textures がカメラの深度テクスチャに TEXTURE_BINDING を追加したことを示すマーカー
+// Outlined がなくなった時に、追加したカメラからだけ取り除いて元に戻すために使う
+//
+#[derive(Component, Clone, Copy, Default)]
+pub struct OutlineDepthTextureUsage;
+
+//
+// レンダーワールドに抽出したアウトライン対象のメッシュ
+//
+pub struct ExtractedOutline {
+    pub mesh: AssetId<Mesh>
+    , pub world_from_local: Mat4
+    , pub color: LinearRgba
+    , pub width: f32
+}
+
+#[derive(Resource, Default)]
+pub struct ExtractedOutlines {
+    pub objects: Vec<ExtractedOutline>
+}
+
+//
+// GPU に渡す構造体（dead_code を抑制する理由は components.rs の uniforms と同じ）
+//
+#[allow(dead_code)]
+mod uniforms {
+    use bevy::{prelude::*, render::render_resource::ShaderType};
+    use crate::consts::app::MAX_OUTLINED_OBJECTS;
+
+    //
+    // マスクの描画で使うビューごとのユニフォーム
+    //
+    #[derive(Clone, Copy, ShaderType)]
+    pub struct OutlineViewUniform {
+        pub clip_from_world: Mat4
+    }
+
+    //
+    // マスクの描画で使うオブジェクトごとのユニフォーム
+    //
+    #[derive(Clone, Copy, ShaderType)]
+    pub struct OutlineObjectUniform {
+        pub world_from_local: Mat4
+        , pub id:     u32 // マスクに書き込む ID（1 始まり）
+        , pub _pad_0: u32
+        , pub _pad_1: u32
+        , pub _pad_2: u32
+    }
+
+    //
+    // アウトラインの描画で使うオブジェクトごとの設定
+    // ※ outline.wgsl の OutlineEntry と同じ並びにすること
+    //
+    #[derive(Clone, Copy, Default, ShaderType)]
+    pub struct OutlineEntry {
+        pub color: Vec4   // 線形 RGB、a は合成の強さ
+        , pub width: f32
+        , pub _pad_0: f32
+        , pub _pad_1: f32
+        , pub _pad_2: f32
+    }
+
+    //
+    // アウトラインの描画で使うユニフォーム
+    // ※ outline.wgsl の OutlineSettings と同じ並びにすること
+    //
+    #[derive(Clone, ShaderType)]
+    pub struct OutlineUniform {
+        pub count: u32
+        , pub max_width: f32
+        , pub _pad_0: u32
+        , pub _pad_1: u32
+        , pub objects: [OutlineEntry; MAX_OUTLINED_OBJECTS]
+    }
+
+    //
+    // ジャンプフラッディングの1パスごとのユニフォーム
+    // ※ jump_flood.wgsl の JumpFlood と同じ並びにすること
+    //
+    #[derive(Clone, Copy, ShaderType)]
+    pub struct JumpFloodUniform {
+        pub step: u32   // このパスで参照する周囲のピクセルとの間隔（アウトラインの描画では 0）
+        , pub glow: f32 // アウトラインの外側に広がる光の長さ（ピクセル）
+        , pub _pad_0: u32
+        , pub _pad_1: u32
+    }
+}
+pub use uniforms::*;
+
+impl Default for OutlineUniform {
+    fn default() -> Self {
+        Self {
+            count: 0
+            , max_width: 0.0
+            , _pad_0: 0
+            , _pad_1: 0
+            , objects: [OutlineEntry::default(); MAX_OUTLINED_OBJECTS]
+        }
+    }
+}
+
+//
+// マスクに描画するメッシュ1つ分の情報
+//
+pub struct OutlineDraw {
+    pub mesh: AssetId<Mesh>
+    , pub pipeline_ids: [Option<CachedRenderPipelineId>; 2] // シーンの深度がマルチサンプルでない／あるビュー用（使うビューがない方は None）
+    , pub object_offset: u32 // objects の動的オフセット
+}
+
+//
+// アウトラインの描画に使うバッファを持つリソース（毎フレーム作り直す）
+//
+#[derive(Resource, Default)]
+pub struct OutlineBuffers {
+    pub views: DynamicUniformBuffer<OutlineViewUniform>
+    , pub objects: DynamicUniformBuffer<OutlineObjectUniform>
+    , pub settings: UniformBuffer<OutlineUniform>
+    , pub jump_flood: DynamicUniformBuffer<JumpFloodUniform>
+    , pub draws: Vec<OutlineDraw>
+}
+
+//
+// ビューごとのマスクのテクスチャ
+//
+#[derive(Component)]
+pub struct ViewOutlineMask {
+    pub mask: CachedTexture   // オブジェクトの ID（R32Uint）
+    , pub depth: CachedTexture // マスクの描画で手前のオブジェクトを残すための深度
+    , pub view_offset: u32    // views の動的オフセット
+    , pub multisampled: bool  // シーンの深度がマルチサンプルかどうか
+    , pub jump_flood: Option<ViewJumpFlood> // アウトラインの描き方がジャンプフラッディングの場合だけ用意する
+}
+
+//
+// ビューごとのジャンプフラッディングのテクスチャとパスごとのユニフォームのオフセット
+//
+pub struct ViewJumpFlood {
+    pub textures: [CachedTexture; 2] // 最も近い種の座標（Rg32Uint）を交互に読み書きする
+    , pub jump_offsets: Vec<u32>     // 間隔の大きい順の各パスの jump_flood の動的オフセット
+    , pub outline_offset: u32        // アウトラインの描画の jump_flood の動的オフセット
+}
+
+//
+// マスクを描画するパイプラインを保持するリソース
+// メッシュの頂点レイアウトと、シーンの深度がマルチサンプルかどうかごとに特殊化する
+//
+#[derive(Resource)]
+pub struct OutlineMaskPipeline {
+    pub view_layout: BindGroupLayout              // シーンの深度がマルチサンプルでないビュー用
+    , pub multisampled_view_layout: BindGroupLayout // シーンの深度がマルチサンプルのビュー用
+    , pub object_layout: BindGroupLayout
+    , pub shader: Handle<Shader>
+}
+
+impl OutlineMaskPipeline {
+    pub fn view_layout(&self, multisampled: bool) -> &BindGroupLayout {
+        if multisampled { &self.multisampled_view_layout } else { &self.view_layout }
+    }
+}
+
+impl FromWorld for OutlineMaskPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let view_layout = render_device.create_bind_group_layout(
+            "outline_mask_view_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::VERTEX_FRAGMENT,
+                (
+                    uniform_buffer::<OutlineViewUniform>(true)
+                    , texture_depth_2d()
+                ),
+            )
+        );
+        let multisampled_view_layout = render_device.create_bind_group_layout(
+            "outline_mask_multisampled_view_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::VERTEX_FRAGMENT,
+                (
+                    uniform_buffer::<OutlineViewUniform>(true)
+                    , texture_depth_2d_multisampled()
+                ),
+            )
+        );
+        let object_layout = render_device.create_bind_group_layout(
+            "outline_mask_object_bind_group_layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::VERTEX_FRAGMENT
+                , uniform_buffer::<OutlineObjectUniform>(true)
+            )
+        );
+
+        Self {
+            view_layout
+            , multisampled_view_layout
+            , object_layout
+            , shader: world.load_asset(OUTLINE_MASK_SHADER_PATH)
+        }
+    }
+}
+
+//
+// マスクのパイプラインの特殊化のキー
+//
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct OutlineMaskPipelineKey {
+    pub topology: PrimitiveTopology
+    , pub multisampled: bool // シーンの深度がマルチサンプルかどうか（MULTISAMPLED）
+}
+
+impl SpecializedMeshPipeline for OutlineMaskPipeline {
+    type Key = OutlineMaskPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayoutRef,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        // 位置だけを使う
+        let vertex_buffer_layout = layout.0.get_layout(&[Mesh::ATTRIBUTE_POSITION.at_shader_location(0)])?;
+        let shader_defs: Vec<ShaderDefVal> = if key.multisampled { vec!["MULTISAMPLED".into()] } else { vec![] };
+
+        Ok(RenderPipelineDescriptor {
+            label: Some("outline_mask_pipeline".into())
+            , layout: vec![self.view_layout(key.multisampled).clone(), self.object_layout.clone()]
+            , vertex: VertexState {
+                shader: self.shader.clone()
+                , shader_defs: shader_defs.clone()
+                , entry_point: "vertex".into()
+                , buffers: vec![vertex_buffer_layout]
+            }
+            , fragment: Some(FragmentState {
+                shader: self.shader.clone()
+                , shader_defs
+                , entry_point: "fragment".into()
+                , targets: vec![Some(ColorTargetState {
+                    format: TextureFormat::R32Uint
+                    , blend: None
+                    , write_mask: ColorWrites::ALL
+                })]
+            })
+            , primitive: PrimitiveState {
+                topology: key.topology
+                , ..default()
+            }
+            // reverse-Z なので大きい方が手前
+            , depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float
+                , depth_write_enabled: true
+                , depth_compare: CompareFunction::GreaterEqual
+                , stencil: StencilState::default()
+                , bias: DepthBiasState::default()
+            })
+            , multisample: MultisampleState::default()
+            , push_constant_ranges: vec![]
+            , zero_initialize_workgroup_memory: false
+        })
+    }
+}
+
+//
+// マスクからアウトラインを描画するパイプラインを保持するリソース
+//
+#[derive(Resource)]
+pub struct OutlinePipeline {
+    pub layout: BindGroupLayout
+    , pub sampler: Sampler
+    , pub pipeline_ids: ViewFormatPipelineIds // ビューの出力先の形式ごと
+}
+impl FromWorld for OutlinePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let layout = render_device.create_bind_group_layout(
+            "outline_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true })
+                    , sampler(SamplerBindingType::Filtering)
+                    , texture_2d(TextureSampleType::Uint)
+                    , uniform_buffer::<OutlineUniform>(false)
+                ),
+            )
+        );
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+
+        let shader = world.load_asset(OUTLINE_SHADER_PATH);
+        let pipeline_ids = ViewFormatPipelineIds::queue(world.resource::<PipelineCache>(), |format| RenderPipelineDescriptor {
+            label: Some("outline_pipeline".into())
+            , layout: vec![layout.clone()]
+            , vertex: fullscreen_shader_vertex_state()
+            , fragment: Some(FragmentState {
+                shader: shader.clone()
+                , shader_defs: vec![]
+                , entry_point: "fragment".into()
+                , targets: vec![Some(ColorTargetState {
+                    format
+                    , blend: None
+                    , write_mask: ColorWrites::ALL
+                })]
+            })
+            , primitive: PrimitiveState::default()
+            , depth_stencil: None
+            , multisample: MultisampleState::default()
+            , push_constant_ranges: vec![]
+            , zero_initialize_workgroup_memory: false
+        });
+
+        Self {
+            layout
+            , sampler
+            , pipeline_ids
+        }
+    }
+}
+
+//
+// ジャンプフラッディングでアウトラインを描画するパイプラインを保持するリソース
+// 種の初期化、種の伝播、アウトラインの描画の3つのパイプラインを持つ
+//
+#[derive(Resource)]
+pub struct JumpFloodPipeline {
+    pub init_layout: BindGroupLayout
+    , pub jump_layout: BindGroupLayout
+    , pub outline_layout: BindGroupLayout
+    , pub sampler: Sampler
+    , pub init_pipeline_id: CachedRenderPipelineId
+    , pub jump_pipeline_id: CachedRenderPipelineId
+    , pub outline_pipeline_ids: ViewFormatPipelineIds // ビューの出力先の形式ごと
+}
+impl FromWorld for JumpFloodPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        // jump_flood.wgsl のバインディングの番号に合わせる
+        let init_layout = render_device.create_bind_group_layout(
+            "jump_flood_init_bind_group_layout",
+            &BindGroupLayoutEntries::with_indices(
+                ShaderStages::FRAGMENT,
+                ((0, texture_2d(TextureSampleType::Uint)),),
+            )
+        );
+        let jump_layout = render_device.create_bind_group_layout(
+            "jump_flood_bind_group_layout",
+            &BindGroupLayoutEntries::with_indices(
+                ShaderStages::FRAGMENT,
+                (
+                    (1, texture_2d(TextureSampleType::Uint))
+                    , (2, uniform_buffer::<JumpFloodUniform>(true))
+                ),
+            )
+        );
+        let outline_layout = render_device.create_bind_group_layout(
+            "jump_flood_outline_bind_group_layout",
+            &BindGroupLayoutEntries::with_indices(
+                ShaderStages::FRAGMENT,
+                (
+                    (0, texture_2d(TextureSampleType::Uint))
+                    , (1, texture_2d(TextureSampleType::Uint))
+                    , (2, uniform_buffer::<JumpFloodUniform>(true))
+                    , (3, texture_2d(TextureSampleType::Float { filterable: true }))
+                    , (4, sampler(SamplerBindingType::Filtering))
+                    , (5, uniform_buffer::<OutlineUniform>(false))
+                ),
+            )
+        );
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+
+        let shader = world.load_asset(JUMP_FLOOD_SHADER_PATH);
+        let cache = world.resource::<PipelineCache>();
+        let descriptor = |label: &'static str, layout: &BindGroupLayout, entry_point: &'static str, format| {
+            RenderPipelineDescriptor {
+                label: Some(label.into())
+                , layout: vec![layout.clone()]
+                , vertex: fullscreen_shader_vertex_state()
+                , fragment: Some(FragmentState {
+                    shader: shader.clone()
+                    , shader_defs: vec![]
+                    , entry_point: entry_point.into()
+                    , targets: vec![Some(ColorTargetState {
+                        format
+                        , blend: None
+                        , write_mask: ColorWrites::ALL
+                    })]
+                })
+                , primitive: PrimitiveState::default()
+                , depth_stencil: None
+                , multisample: MultisampleState::default()
+                , push_constant_ranges: vec![]
+                , zero_initialize_workgroup_memory: false
+            }
+        };
+        let init_pipeline_id = cache.queue_render_pipeline(descriptor("jump_flood_init_pipeline", &init_layout, "init", TextureFormat::Rg32Uint));
+        let jump_pipeline_id = cache.queue_render_pipeline(descriptor("jump_flood_pipeline", &jump_layout, "jump", TextureFormat::Rg32Uint));
+        let outline_pipeline_ids = ViewFormatPipelineIds::queue(cache, |format| {
+            descriptor("jump_flood_outline_pipeline", &outline_layout, "outline", format)
+        });
+
+        Self {
+            init_layout
+            , jump_layout
+            , outline_layout
+            , sampler
+            , init_pipeline_id
+            , jump_pipeline_id
+            , outline_pipeline_ids
+        }
+    }
+}
+
+//
+// アウトラインを識別するためのラベル
+//
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct OutlineLabel;
+
+//
+// アウトラインのレンダーパイプラインノードの定義
+// Outlined のメッシュの ID をマスクに描画してから、ID の境界にアウトラインを描く
+//
+#[derive(Default)]
+pub struct OutlineNode;
+impl ViewNode for OutlineNode {
+    type ViewQuery = (
+        &'static ViewTarget
+        , &'static ViewOutlineMask
+        , &'static ViewDepthTexture
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, view_mask, view_depth): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let buffers = world.resource::<OutlineBuffers>();
+        if buffers.draws.is_empty() {
+            return Ok(());
+        }
+
+        let mask_pipeline = world.resource::<OutlineMaskPipeline>();
+        let outline_pipeline = world.resource::<OutlinePipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(outline_pipeline.pipeline_ids.get(view_target)) else {
+            return Ok(());
+        };
+        let (Some(views_binding), Some(objects_binding), Some(settings_binding)) = (
+            buffers.views.binding()
+            , buffers.objects.binding()
+            , buffers.settings.binding()
+        ) else {
+            return Ok(());
+        };
+
+        // シーンの深度をシェーダーで読めない場合（require_outline_depth_textures の反映前）は描画しない
+        if !view_depth.texture.usage().contains(TextureUsages::TEXTURE_BINDING) {
+            return Ok(());
+        }
+
+        let render_device = render_context.render_device().clone();
+        let view_bind_group = render_device.create_bind_group(
+            "outline_mask_view_bind_group"
+            , mask_pipeline.view_layout(view_mask.multisampled)
+            , &BindGroupEntries::sequential((views_binding, view_depth.view()))
+        );
+        let object_bind_group = render_device.create_bind_group(
+            "outline_mask_object_bind_group"
+            , &mask_pipeline.object_layout
+            , &BindGroupEntries::single(objects_binding)
+        );
+
+        // マスクの描画
+        {
+            let meshes = world.resource::<RenderAssets<RenderMesh>>();
+            let mesh_allocator = world.resource::<MeshAllocator>();
+            let mut mask_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: Some("outline_mask_pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &view_mask.mask.default_view
+                    , resolve_target: None
+                    , ops: Operations { load: LoadOp::Clear(default()), store: StoreOp::Store }
+                })]
+                , depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &view_mask.depth.default_view
+                    , depth_ops: Some(Operations { load: LoadOp::Clear(0.0), store: StoreOp::Discard })
+                    , stencil_ops: None
+                })
+                , timestamp_writes: None
+                , occlusion_query_set: None
+            });
+            mask_pass.set_bind_group(0, &view_bind_group, &[view_mask.view_offset]);
+
+            for draw in &buffers.draws {
+                let (Some(mesh_pipeline), Some(mesh), Some(vertex_slice)) = (
+                    draw.pipeline_ids[view_mask.multisampled as usize]
+                        .and_then(|pipeline_id| pipeline_cache.get_render_pipeline(pipeline_id))
+                    , meshes.get(draw.mesh)
+                    , mesh_allocator.mesh_vertex_slice(&draw.mesh)
+                ) else {
+                    continue;
+                };
+
+                mask_pass.set_render_pipeline(mesh_pipeline);
+                mask_pass.set_bind_group(1, &object_bind_group, &[draw.object_offset]);
+                mask_pass.set_vertex_buffer(0, vertex_slice.buffer.slice(..));
+                match &mesh.buffer_info {
+                    RenderMeshBufferInfo::Indexed { count, index_format } => {
+                        let Some(index_slice) = mesh_allocator.mesh_index_slice(&draw.mesh) else {
+                            continue;
+                        };
+                        mask_pass.set_index_buffer(index_slice.buffer.slice(..), 0, *index_format);
+                        mask_pass.draw_indexed(
+                            index_slice.range.start..(index_slice.range.start + *count)
+                            , vertex_slice.range.start as i32
+                            , 0..1
+                        );
+                    }
+                    RenderMeshBufferInfo::NonIndexed => {
+                        mask_pass.draw(vertex_slice.range, 0..1);
+                    }
+                }
+            }
+        }
+
+        if let Some(jump_flood) = &view_mask.jump_flood {
+            return run_jump_flood(render_context, view_target, view_mask, jump_flood, world);
+        }
+
+        // マスクからアウトラインを描画
+        let post_process = view_target.post_process_write();
+        let bind_group = render_device.create_bind_group(
+            "outline_bind_group"
+            , &outline_pipeline.layout
+            , &BindGroupEntries::sequential((
+                post_process.source
+                , &outline_pipeline.sampler
+                , &view_mask.mask.default_view
+                , settings_binding
+            ))
+        );
+
+        draw_fullscreen_pass(render_context, "outline_pass", post_process.destination, pipeline, &bind_group, &[]);
+
+        Ok(())
+    }
+}
+
+//
+// ジャンプフラッディングでマスクから距離場を作ってアウトラインを描画する
+//
+fn run_jump_flood(
+    render_context: &mut RenderContext
+    , view_target: &ViewTarget
+    , view_mask: &ViewOutlineMask
+    , jump_flood: &ViewJumpFlood
+    , world: &World
+) -> Result<(), NodeRunError> {
+    let buffers = world.resource::<OutlineBuffers>();
+    let jump_flood_pipeline = world.resource::<JumpFloodPipeline>();
+    let pipeline_cache = world.resource::<PipelineCache>();
+    let (Some(init_pipeline), Some(jump_pipeline), Some(outline_pipeline)) = (
+        pipeline_cache.get_render_pipeline(jump_flood_pipeline.init_pipeline_id)
+        , pipeline_cache.get_render_pipeline(jump_flood_pipeline.jump_pipeline_id)
+        , pipeline_cache.get_render_pipeline(jump_flood_pipeline.outline_pipeline_ids.get(view_target))
+    ) else {
+        return Ok(());
+    };
+    let (Some(jump_flood_binding), Some(settings_binding)) = (
+        buffers.jump_flood.binding()
+        , buffers.settings.binding()
+    ) else {
+        return Ok(());
+    };
+
+    let render_device = render_context.render_device().clone();
+    // マスクの ID があるピクセルを種にする
+    let init_bind_group = render_device.create_bind_group(
+        "jump_flood_init_bind_group"
+        , &jump_flood_pipeline.init_layout
+        , &BindGroupEntries::with_indices(((0, &view_mask.mask.default_view),))
+    );
+    draw_fullscreen_pass(render_context, "jump_flood_init_pass", &jump_flood.textures[0].default_view, init_pipeline, &init_bind_group, &[]);
+
+    // 間隔を半分にしながら種を伝播させる（テクスチャを交互に読み書きする）
+    let mut current = 0;
+    for &offset in &jump_flood.jump_offsets {
+        let bind_group = render_device.create_bind_group(
+            "jump_flood_bind_group"
+            , &jump_flood_pipeline.jump_layout
+            , &BindGroupEntries::with_indices((
+                (1, &jump_flood.textures[current].default_view)
+                , (2, jump_flood_binding.clone())
+            ))
+        );
+        draw_fullscreen_pass(render_context, "jump_flood_pass", &jump_flood.textures[1 - current].default_view, jump_pipeline, &bind_group, &[offset]);
+        current = 1 - current;
+    }
+
+    // 最も近い種までの距離からアウトラインを描画
+    let post_process = view_target.post_process_write();
+    let outline_bind_group = render_device.create_bind_group(
+        "jump_flood_outline_bind_group"
+        , &jump_flood_pipeline.outline_layout
+        , &BindGroupEntries::with_indices((
+            (0, &view_mask.mask.default_view)
+            , (1, &jump_flood.textures[current].default_view)
+            , (2, jump_flood_binding)
+            , (3, post_process.source)
+            , (4, &jump_flood_pipeline.sampler)
+            , (5, settings_binding)
+        ))
+    );
+    draw_fullscreen_pass(render_context, "jump_flood_outline_pass", post_process.destination, outline_pipeline, &outline_bind_group, &[jump_flood.outline_offset]);
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+use bevy::{
+    prelude::*
+    , asset::{io::Reader, AssetLoader, LoadContext}
+    , ecs::system::{lifetimeless::SRes, SystemParamItem}
+    , render::{
+        render_asset::{PrepareAssetError, RenderAsset}
+        , render_resource::*
+        , renderer::{RenderDevice, RenderQueue}
+    }
+};
+
+use crate::plugins::functions::palette::*;
+
+//
+// 減色パレットのアセット
+//
+#[derive(Asset, TypePath, Clone, Default, Debug)]
+pub struct Palette {
+    pub colors: Vec<Color>
+}
+
+//
+// パレットファイルの読み込みに失敗した時のエラー
+//
+#[derive(Debug)]
+pub enum PaletteLoaderError {
+    Io(std::io::Error)                 // ファイルの読み込みに失敗した
+    , Image(image::ImageError)         // PNG のデコードに失敗した
+    , Parse { line: usize, message: String } // テキスト形式の解析に失敗した（line は1始まりの行番号）
+    , UnsupportedFormat(String)        // 対応していない拡張子
+}
+
+impl fmt::Display for PaletteLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteLoaderError::Io(error) => write!(f, "could not read palette: {error}")
+            , PaletteLoaderError::Image(error) => write!(f, "could not decode palette image: {error}")
+            , PaletteLoaderError::Parse { line, message } => write!(f, "invalid palette at line {line}: {message}")
+            , PaletteLoaderError::UnsupportedFormat(extension) => write!(f, "unsupported palette format: {extension}")
+        }
+    }
+}
+
+impl std::error::Error for PaletteLoaderError {}
+
+impl From<std::io::Error> for PaletteLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        PaletteLoaderError::Io(error)
+    }
+}
+
+impl From<image::ImageError> for PaletteLoaderError {
+    fn from(error: image::ImageError) -> Self {
+        PaletteLoaderError::Image(error)
+    }
+}
+
+//
+// パレットファイルのローダー
+// GIMP（.gpl）、Lospec の HEX（.hex）、JASC（.pal）、1ピクセル高さの PNG に対応する
+// ※ PNG は画像のローダーと拡張子が重なるため extensions には含めていない
+//    asset_server.load::<Palette>("palettes/xxx.png") のように型を指定して読み込むこと
+//
+#[derive(Default)]
+pub struct PaletteLoader;
+
+impl AssetLoader for PaletteLoader {
+    type Asset = Palette;
+    type Settings = ();
+    type Error = PaletteLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Palette, PaletteLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let extension = load_context.path()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        let colors = match extension.as_str() {
+            "gpl"   => parse_gpl(&String::from_utf8_lossy(&bytes))?
+            , "hex" => parse_hex(&String::from_utf8_lossy(&bytes))?
+            , "pal" => parse_jasc_pal(&String::from_utf8_lossy(&bytes))?
+            , "png" => parse_png(&bytes)?
+            , _     => return Err(PaletteLoaderError::UnsupportedFormat(extension))
+        };
+
+        Ok(Palette { colors })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["gpl", "hex", "pal"]
+    }
+}
+
+//
+// パレットの GPU 側の表現
+// 1行目が線形 RGB、2行目が OKLab のテクスチャ
+// アセットが変更（ホットリロード）されると自動で作り直される
+//
+pub struct GpuPalette {
+    pub texture: Texture
+    , pub view: TextureView
+}
+
+impl RenderAsset for GpuPalette {
+    type SourceAsset = Palette;
+    type Param = (SRes<RenderDevice>, SRes<RenderQueue>);
+
+    fn prepare_asset(
+        palette: Palette,
+        _asset_id: AssetId<Palette>,
+        (render_device, render_queue): &mut SystemParamItem<Self::Param>,
+    ) -> Result<Self, PrepareAssetError<Palette>> {
+        let colors = usable_palette_colors(&palette.colors);
+        let texture = create_palette_texture(render_device, render_queue, colors);
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        Ok(GpuPalette { texture, view })
+    }
+}
@@ -0,0 +1,81 @@
+use bevy::{
+    prelude::*
+    , core_pipeline::{
+        core_2d::graph::Node2d
+        , core_3d::graph::Node3d
+    }
+    , render::render_graph::{InternedRenderLabel, RenderLabel}
+    , ui::graph::NodeUi
+};
+
+//
+// ポストプロセスのノードをレンダーグラフのどこに置くか
+//
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum PostProcessPlacement {
+    BeforeTonemapping   // トーンマッピングの前（HDR のカメラでは線形の HDR の値にディザをかける）
+    , #[default]
+    AfterTonemapping    // トーンマッピングの後
+    , AfterAntiAliasing // FXAA/SMAA（とコントラスト適応シャープニング）の後
+    , AfterUi           // UI の描画の後（UI を含めた画面全体にディザをかける）
+}
+
+//
+// ノードの前後に繋ぐノード
+// after のノードの後、before のノードの前に置く（グラフに存在しないノードは無視される）
+//
+pub struct PlacementAnchors {
+    pub after: Vec<InternedRenderLabel>
+    , pub before: Vec<InternedRenderLabel>
+}
+
+impl PostProcessPlacement {
+    // トーンマッピング前の値を受け取るかどうか
+    pub fn is_before_tonemapping(&self) -> bool {
+        *self == PostProcessPlacement::BeforeTonemapping
+    }
+
+    // 3D のグラフでの前後のノード
+    pub fn anchors_3d(&self) -> PlacementAnchors {
+        match self {
+            PostProcessPlacement::BeforeTonemapping => PlacementAnchors {
+                after: vec![Node3d::EndMainPass.intern(), Node3d::Bloom.intern(), Node3d::DepthOfField.intern(), Node3d::PostProcessing.intern()]
+                , before: vec![Node3d::Tonemapping.intern()]
+            }
+            , PostProcessPlacement::AfterTonemapping => PlacementAnchors {
+                after: vec![Node3d::Tonemapping.intern()]
+                , before: vec![Node3d::EndMainPassPostProcessing.intern()]
+            }
+            , PostProcessPlacement::AfterAntiAliasing => PlacementAnchors {
+                after: vec![Node3d::Tonemapping.intern(), Node3d::Fxaa.intern(), Node3d::Smaa.intern(), Node3d::ContrastAdaptiveSharpening.intern()]
+                , before: vec![Node3d::EndMainPassPostProcessing.intern()]
+            }
+            , PostProcessPlacement::AfterUi => PlacementAnchors {
+                after: vec![NodeUi::UiPass.intern()]
+                , before: vec![Node3d::Upscaling.intern()]
+            }
+        }
+    }
+
+    // 2D のグラフでの前後のノード
+    pub fn anchors_2d(&self) -> PlacementAnchors {
+        match self {
+            PostProcessPlacement::BeforeTonemapping => PlacementAnchors {
+                after: vec![Node2d::EndMainPass.intern(), Node2d::Bloom.intern(), Node2d::PostProcessing.intern()]
+                , before: vec![Node2d::Tonemapping.intern()]
+            }
+            , PostProcessPlacement::AfterTonemapping => PlacementAnchors {
+                after: vec![Node2d::Tonemapping.intern()]
+                , before: vec![Node2d::EndMainPassPostProcessing.intern()]
+            }
+            , PostProcessPlacement::AfterAntiAliasing => PlacementAnchors {
+                after: vec![Node2d::Tonemapping.intern(), Node2d::Fxaa.intern(), Node2d::Smaa.intern(), Node2d::ContrastAdaptiveSharpening.intern()]
+                , before: vec![Node2d::EndMainPassPostProcessing.intern()]
+            }
+            , PostProcessPlacement::AfterUi => PlacementAnchors {
+                after: vec![NodeUi::UiPass.intern()]
+                , before: vec![Node2d::Upscaling.intern()]
+            }
+        }
+    }
+}
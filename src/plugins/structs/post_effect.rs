@@ -0,0 +1,503 @@
+use std::{
+    any::{Any, TypeId}
+    , num::NonZeroU64
+};
+use bevy::{
+    prelude::*
+    , ecs::query::QueryItem
+    , platform::collections::HashMap
+    , render::{
+        extract_component::ExtractComponent
+        , render_graph::{
+            NodeRunError
+            , RenderGraphContext
+            , RenderLabel
+            , ViewNode
+        }
+        , render_resource::{
+            encase::{internal::WriteInto, UniformBuffer}
+            , *
+        }
+        , extract_component::DynamicUniformIndex
+        , renderer::RenderContext
+        , view::ViewTarget
+    }
+};
+use crate::plugins::structs::fullscreen::*;
+use crate::plugins::structs::components::PostProcessUniform;
+use crate::plugins::structs::post_processes::ViewPostProcessPipeline;
+use crate::plugins::structs::bind_group_cache::PostProcessBindGroupCache;
+
+//
+// ポストエフェクトのスタックに積める効果のトレイト
+// 効果ごとに1枚のフラグメントシェーダーで全画面を描画し、前の効果の結果を入力として受け取る
+// ※ 対象はユニフォームと固定の追加バインディングだけで描ける効果（色調補正など）
+//    ディザ・エッジはプリパスやパレット、シェーダー定義による特殊化が必要なため PostProcessSettings のパスで描く
+//    スタックには PostProcessStage として積み、他の効果と同じように並べ替えられる
+//
+// シェーダーのバインディングは FullscreenPassLayout の並びで、ユニフォームには Uniform、
+// @binding(3) 以降には extra_layout_entries で追加したものが入る
+//
+pub trait PostEffect: Clone + Send + Sync + 'static {
+    type Uniform: ShaderType + WriteInto;
+
+    // 効果のシェーダー（エントリーポイントは "fragment"）
+    fn shader() -> ShaderRef;
+
+    // カメラごとの設定からシェーダーに渡すユニフォームを作る
+    fn uniform(&self) -> Self::Uniform;
+
+    // 追加のバインディングのレイアウト（テクスチャなど）
+    fn extra_layout_entries() -> Vec<BindGroupLayoutEntryBuilder> {
+        Vec::new()
+    }
+
+    // 追加のバインディング（レンダーワールドのリソースから取り出す、extra_layout_entries と同じ数と順にすること）
+    fn extra_bindings(_world: &World) -> Vec<BindingResource<'_>> {
+        Vec::new()
+    }
+}
+
+//
+// 型を消したポストエフェクト（スタックに異なる種類の効果を並べるために使う）
+//
+pub trait ErasedPostEffect: Send + Sync + 'static {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn effect_type(&self) -> TypeId;
+    fn uniform_bytes(&self) -> Vec<u8>;
+    fn clone_box(&self) -> Box<dyn ErasedPostEffect>;
+}
+
+impl<E: PostEffect> ErasedPostEffect for E {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn effect_type(&self) -> TypeId {
+        TypeId::of::<E>()
+    }
+
+    fn uniform_bytes(&self) -> Vec<u8> {
+        let mut buffer = UniformBuffer::new(Vec::new());
+        buffer.write(&self.uniform()).expect("post effect uniform could not be written");
+        buffer.into_inner()
+    }
+
+    fn clone_box(&self) -> Box<dyn ErasedPostEffect> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn ErasedPostEffect> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+//
+// PostProcessSettings のパスのうちスタックの中に並べられる段階
+// スタックに積むとその位置でカメラの PostProcessSettings の該当する部分だけを描き、
+// 積まれていない段階は今まで通りスタックの後の PostProcessNode でまとめて描く
+// ※ 誤差拡散の量子化は後段のコンピュートシェーダーで行うので、Dither の位置によらずスタックの後になる
+//    PostProcessSettings のないカメラでは飛ばされる
+//
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PostProcessStage {
+    Dither  // ディザ・ハーフトーン・パレットによる減色
+    , Edges // エッジの検出と描画
+}
+
+impl PostProcessStage {
+    pub const ALL: [PostProcessStage; 2] = [PostProcessStage::Dither, PostProcessStage::Edges];
+}
+
+//
+// スタックの1段（効果か PostProcessSettings の段階）
+//
+#[derive(Clone)]
+enum PostEffectEntry {
+    Effect(Box<dyn ErasedPostEffect>)
+    , Stage(PostProcessStage)
+}
+
+//
+// カメラごとのポストエフェクトのスタック
+// 先頭から順に1段ずつ ViewTarget::post_process_write で入出力を入れ替えながら描画する
+// ※ 効果の種類ごとに PostEffectPlugin::<E> を登録しておくこと（未登録の効果は飛ばされる）
+//
+// PostEffectStack::new()
+//     .with_stage(PostProcessStage::Edges)
+//     .with(Grading::default())
+//     .with_stage(PostProcessStage::Dither)
+//
+#[derive(Component, Clone, Default)]
+pub struct PostEffectStack {
+    effects: Vec<PostEffectEntry>
+}
+
+impl PostEffectStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 末尾に効果を追加したスタックを返す（spawn 時に並べて書くため）
+    pub fn with<E: PostEffect>(mut self, effect: E) -> Self {
+        self.push(effect);
+        self
+    }
+
+    pub fn push<E: PostEffect>(&mut self, effect: E) {
+        self.effects.push(PostEffectEntry::Effect(Box::new(effect)));
+    }
+
+    // index 番目に効果を挿入する（範囲外の場合は末尾に追加する）
+    pub fn insert<E: PostEffect>(&mut self, index: usize, effect: E) {
+        self.insert_entry(index, PostEffectEntry::Effect(Box::new(effect)));
+    }
+
+    // 末尾に段階を追加したスタックを返す
+    pub fn with_stage(mut self, stage: PostProcessStage) -> Self {
+        self.push_stage(stage);
+        self
+    }
+
+    pub fn push_stage(&mut self, stage: PostProcessStage) {
+        self.effects.push(PostEffectEntry::Stage(stage));
+    }
+
+    // index 番目に段階を挿入する（範囲外の場合は末尾に追加する）
+    pub fn insert_stage(&mut self, index: usize, stage: PostProcessStage) {
+        self.insert_entry(index, PostEffectEntry::Stage(stage));
+    }
+
+    fn insert_entry(&mut self, index: usize, entry: PostEffectEntry) {
+        let index = index.min(self.effects.len());
+        self.effects.insert(index, entry);
+    }
+
+    // index 番目の効果か段階を取り除く（範囲外の場合は何もしない）
+    pub fn remove(&mut self, index: usize) {
+        if index < self.effects.len() {
+            self.effects.remove(index);
+        }
+    }
+
+    // 効果か段階の順番を入れ替える（どちらかが範囲外の場合は何もしない）
+    pub fn swap(&mut self, a: usize, b: usize) {
+        if a < self.effects.len() && b < self.effects.len() {
+            self.effects.swap(a, b);
+        }
+    }
+
+    // 最初に見つかった E の位置
+    pub fn position<E: PostEffect>(&self) -> Option<usize> {
+        self.effects.iter().position(|entry| {
+            matches!(entry, PostEffectEntry::Effect(effect) if effect.effect_type() == TypeId::of::<E>())
+        })
+    }
+
+    // 最初に見つかった stage の位置
+    pub fn stage_position(&self, stage: PostProcessStage) -> Option<usize> {
+        self.effects.iter().position(|entry| matches!(entry, PostEffectEntry::Stage(entry_stage) if *entry_stage == stage))
+    }
+
+    pub fn get<E: PostEffect>(&self) -> Option<&E> {
+        self.effects.iter().find_map(|entry| match entry {
+            PostEffectEntry::Effect(effect) => effect.as_any().downcast_ref::<E>()
+            , PostEffectEntry::Stage(_) => None
+        })
+    }
+
+    pub fn get_mut<E: PostEffect>(&mut self) -> Option<&mut E> {
+        self.effects.iter_mut().find_map(|entry| match entry {
+            PostEffectEntry::Effect(effect) => effect.as_any_mut().downcast_mut::<E>()
+            , PostEffectEntry::Stage(_) => None
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+}
+
+impl ExtractComponent for PostEffectStack {
+    type QueryData = &'static Self;
+    type QueryFilter = ();
+    type Out = ExtractedPostEffectStack;
+
+    fn extract_component(stack: QueryItem<'_, Self::QueryData>) -> Option<Self::Out> {
+        Some(ExtractedPostEffectStack {
+            effects: stack.effects
+                .iter()
+                .map(|entry| match entry {
+                    PostEffectEntry::Effect(effect) => ExtractedPostEffect::Effect {
+                        effect_type: effect.effect_type()
+                        , uniform: effect.uniform_bytes()
+                    }
+                    , PostEffectEntry::Stage(stage) => ExtractedPostEffect::Stage(*stage)
+                })
+                .collect()
+        })
+    }
+}
+
+//
+// レンダーワールドに抽出したポストエフェクト（効果の種類とユニフォームのバイト列、または段階）
+//
+pub enum ExtractedPostEffect {
+    Effect {
+        effect_type: TypeId
+        , uniform: Vec<u8>
+    }
+    , Stage(PostProcessStage)
+}
+
+#[derive(Component)]
+pub struct ExtractedPostEffectStack {
+    pub effects: Vec<ExtractedPostEffect>
+}
+
+impl ExtractedPostEffectStack {
+    pub fn has_stage(&self, stage: PostProcessStage) -> bool {
+        self.effects.iter().any(|effect| matches!(effect, ExtractedPostEffect::Stage(effect_stage) if *effect_stage == stage))
+    }
+}
+
+//
+// すべてのビューのスタックの効果のユニフォームをまとめて書き込むバッファ
+// 効果ごとに型が違うのでバイト列のまま動的オフセットのアライメントに揃えて並べる
+// バッファは足りなくなった時だけ作り直し、それ以外はフレームをまたいで使い回す
+//
+#[derive(Resource)]
+pub struct PostEffectUniforms {
+    pub buffer: RawBufferVec<u8>
+}
+
+impl Default for PostEffectUniforms {
+    fn default() -> Self {
+        let mut buffer = RawBufferVec::new(BufferUsages::UNIFORM);
+        buffer.set_label(Some("post_effect_uniform_buffer"));
+        Self { buffer }
+    }
+}
+
+//
+// ビューごとのスタックの効果のユニフォームの PostEffectUniforms での動的オフセット
+// 段階は PostProcessSettings のユニフォームを使うので 0 が入る
+//
+#[derive(Component)]
+pub struct ViewPostEffectOffsets {
+    pub offsets: Vec<u32>
+}
+
+//
+// 効果1種類分のパイプライン
+//
+pub struct PostEffectPipeline {
+    pub pass_layout: FullscreenPassLayout
+    , pub pipeline_ids: ViewFormatPipelineIds
+    , pub uniform_size: NonZeroU64 // ユニフォームのバインディングの大きさ
+    , pub has_extra_bindings: bool
+    , pub extra_bindings: for<'w> fn(&'w World) -> Vec<BindingResource<'w>>
+}
+
+//
+// 登録された効果のパイプラインを効果の型ごとに持つリソース
+//
+#[derive(Resource, Default)]
+pub struct PostEffectPipelines {
+    pub pipelines: HashMap<TypeId, PostEffectPipeline>
+}
+
+//
+// ポストエフェクトのスタックを識別するためのラベル
+//
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub struct PostEffectStackLabel;
+
+//
+// バインドグループにバインドしたリソースの ID と効果の種類
+// どれかが変わった場合のみバインドグループを作り直す
+//
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PostEffectBindGroupKey {
+    pub source: TextureViewId // 入力の画面のテクスチャ（ViewTarget の2枚のメインテクスチャのどちらか）
+    , pub buffer: BufferId    // PostEffectUniforms のバッファ
+    , pub effect_type: TypeId
+}
+
+pub struct CachedPostEffectBindGroup {
+    pub key: PostEffectBindGroupKey
+    , pub bind_group: BindGroup
+}
+
+//
+// ビューごと、スタックの効果の位置ごとにキャッシュしたバインドグループ
+// 入力になりうる2枚のメインテクスチャのそれぞれについて用意する
+// prepare_post_effect_bind_groups で毎フレームその時のビューから作り直すので、なくなったビューや効果の分は残らない
+//
+#[derive(Resource, Default)]
+pub struct PostEffectBindGroupCache {
+    pub views: HashMap<Entity, Vec<Vec<CachedPostEffectBindGroup>>>
+}
+
+impl PostEffectBindGroupCache {
+    // ビューの index 番目の効果の、入力のテクスチャに対応するバインドグループ
+    pub fn get(&self, view: Entity, index: usize, source: &TextureView) -> Option<&BindGroup> {
+        self.views
+            .get(&view)?
+            .get(index)?
+            .iter()
+            .find(|cached| cached.key.source == source.id())
+            .map(|cached| &cached.bind_group)
+    }
+}
+
+//
+// ポストエフェクトのスタックのレンダーパイプラインノードの定義
+// 効果のバインドグループは prepare_post_effect_bind_groups でビューと効果の位置ごとに用意済み
+// 段階は prepare_post_process_pipelines で段階ごとに特殊化したパイプラインと、ポストプロセスのバインドグループで描く
+//
+#[derive(Default)]
+pub struct PostEffectStackNode;
+impl ViewNode for PostEffectStackNode {
+    type ViewQuery = (
+        &'static ViewTarget
+        , &'static ExtractedPostEffectStack
+        , &'static ViewPostEffectOffsets
+        , Option<&'static ViewPostProcessPipeline>
+        , Option<&'static DynamicUniformIndex<PostProcessUniform>>
+    );
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, stack, offsets, view_pipeline, settings_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let post_effect_pipelines = world.resource::<PostEffectPipelines>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let bind_groups = world.resource::<PostEffectBindGroupCache>();
+        let post_process_bind_groups = world.resource::<PostProcessBindGroupCache>();
+        let view_entity = graph.view_entity();
+
+        for (index, (effect, &offset)) in stack.effects.iter().zip(&offsets.offsets).enumerate() {
+            // post_process_write は入力と出力を入れ替えるので、使うパイプラインとバインドグループが見つかってから呼ぶ
+            let (label, pipeline, bind_group, offset) = match effect {
+                ExtractedPostEffect::Effect { effect_type, .. } => {
+                    let Some(effect_pipeline) = post_effect_pipelines.pipelines.get(effect_type) else {
+                        continue;
+                    };
+                    let Some(pipeline) = pipeline_cache.get_render_pipeline(effect_pipeline.pipeline_ids.get(view_target)) else {
+                        continue;
+                    };
+                    let Some(bind_group) = bind_groups.get(view_entity, index, view_target.main_texture_view()) else {
+                        continue;
+                    };
+                    ("post_effect_pass", pipeline, bind_group, offset)
+                }
+                , ExtractedPostEffect::Stage(stage) => {
+                    let (Some(view_pipeline), Some(settings_index)) = (view_pipeline, settings_index) else {
+                        continue;
+                    };
+                    let Some(pipeline) = view_pipeline.stage_id(*stage).and_then(|id| view_pipeline.render_pipeline(id, world)) else {
+                        continue;
+                    };
+                    let Some(bind_group) = post_process_bind_groups.get(view_entity, view_target.main_texture_view()) else {
+                        continue;
+                    };
+                    ("post_process_stage_pass", pipeline, bind_group, settings_index.index())
+                }
+            };
+
+            let post_process = view_target.post_process_write();
+            draw_fullscreen_pass(render_context, label, post_process.destination, pipeline, bind_group, &[offset]);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::render_resource::TextureFormat;
+    use crate::plugins::structs::components::PostProcessSettings;
+    use crate::plugins::structs::grading::Grading;
+    use crate::plugins::structs::post_processes::PostProcessPipelineKey;
+    use crate::plugins::structs::settings::{Dither, Edges};
+    use super::*;
+
+    fn stage_order(stack: &PostEffectStack) -> (Option<usize>, Option<usize>) {
+        (stack.stage_position(PostProcessStage::Edges), stack.stage_position(PostProcessStage::Dither))
+    }
+
+    #[test]
+    fn stages_reorder_with_the_effects() {
+        let mut stack = PostEffectStack::new()
+            .with_stage(PostProcessStage::Edges)
+            .with(Grading::default())
+            .with_stage(PostProcessStage::Dither);
+        assert_eq!(stage_order(&stack), (Some(0), Some(2)));
+        assert_eq!(stack.position::<Grading>(), Some(1));
+
+        stack.swap(0, 2);
+        assert_eq!(stage_order(&stack), (Some(2), Some(0)));
+        assert_eq!(stack.position::<Grading>(), Some(1));
+
+        stack.remove(2);
+        stack.insert_stage(0, PostProcessStage::Edges);
+        assert_eq!(stage_order(&stack), (Some(0), Some(1)));
+        assert!(stack.get::<Grading>().is_some());
+    }
+
+    #[test]
+    fn extracted_stack_keeps_the_stage_positions() {
+        let stack = PostEffectStack::new()
+            .with(Grading::default())
+            .with_stage(PostProcessStage::Dither);
+        let extracted = PostEffectStack::extract_component(&stack).unwrap();
+        assert!(matches!(extracted.effects[0], ExtractedPostEffect::Effect { effect_type, .. } if effect_type == TypeId::of::<Grading>()));
+        assert!(matches!(extracted.effects[1], ExtractedPostEffect::Stage(PostProcessStage::Dither)));
+        assert!(extracted.has_stage(PostProcessStage::Dither));
+        assert!(!extracted.has_stage(PostProcessStage::Edges));
+    }
+
+    #[test]
+    fn stage_pipelines_only_draw_their_own_stage() {
+        let settings = PostProcessSettings::builder()
+            .dither(Dither::ordered())
+            .edges(Edges::default())
+            .build()
+            .unwrap();
+        let defs = |stages: &[PostProcessStage]| {
+            PostProcessPipelineKey::from_settings(AssetId::default(), &settings, stages, false, TextureFormat::bevy_default(), false)
+                .shader_defs()
+                .into_iter()
+                .filter_map(|def| match def {
+                    ShaderDefVal::Bool(name, true) => Some(name)
+                    , _ => None
+                })
+                .collect::<Vec<_>>()
+        };
+        let has = |defs: &[String], name: &str| defs.iter().any(|def| def == name);
+
+        let both = defs(&PostProcessStage::ALL);
+        assert!(has(&both, "DITHER") && has(&both, "EDGE") && !has(&both, "POST_PROCESS_EDGES_ONLY"));
+
+        let dither = defs(&[PostProcessStage::Dither]);
+        assert!(has(&dither, "DITHER") && !has(&dither, "EDGE"));
+
+        let edges = defs(&[PostProcessStage::Edges]);
+        assert!(has(&edges, "EDGE") && has(&edges, "POST_PROCESS_EDGES_ONLY") && !has(&edges, "DITHER"));
+    }
+}
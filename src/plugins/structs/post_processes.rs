@@ -1,13 +1,11 @@
 use bevy::{
     prelude::*
     , asset::Handle
+    , tasks::{AsyncComputeTaskPool, Task}
     , core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state
     , ecs::query::QueryItem
     , render::{
-        extract_component::{
-            ComponentUniforms
-            , DynamicUniformIndex
-        }
+        extract_component::DynamicUniformIndex
         , extract_resource::ExtractResource
         , render_graph::{
             NodeRunError
@@ -16,16 +14,23 @@ use bevy::{
             , ViewNode
         }
         , render_resource::{
-            binding_types::{sampler, texture_2d, uniform_buffer}
+            binding_types::{sampler, texture_2d, texture_depth_2d, uniform_buffer}
             , *
         }
-        , renderer::{RenderContext, RenderDevice}
+        , renderer::{RenderContext, RenderDevice, RenderQueue}
         , view::{ViewTarget}
     }
 };
 
-use crate::plugins::structs::components::PostProcessSettings;
+use crate::plugins::structs::components::{PostProcessSettings, PostProcessUniform};
 use crate::plugins::post_process::PostProcessDefaults;
+use crate::plugins::functions::blue_noise::{blue_noise_image, blue_noise_placeholder_image};
+use crate::plugins::functions::bayer::bayer_image;
+use crate::consts::app::*;
+use crate::plugins::structs::bind_group_cache::PostProcessBindGroupCache;
+use crate::plugins::structs::status::PostProcessShaderStatuses;
+use crate::plugins::structs::post_effect::PostProcessStage;
+use crate::plugins::functions::palette::create_palette_texture;
 
 // ポストプロセスのどのシェーダーを使うかを持つリソース
 #[derive(Resource, Clone, ExtractResource)]
@@ -45,6 +50,67 @@ impl FromWorld for PostProcessShader {
     }
 }
 
+//
+// ポストプロセスのシェーダーが使えない場合に代わりに使う、入力をそのまま出力するシェーダーを持つリソース
+// アセットのパスが間違っていても使えるようにバイナリに埋め込んでおく
+//
+#[derive(Resource, Clone)]
+pub struct PostProcessPassthroughShader(pub Handle<Shader>);
+
+impl FromWorld for PostProcessPassthroughShader {
+    fn from_world(world: &mut World) -> Self {
+        let shader = Shader::from_wgsl(
+            include_str!("../../../assets/shaders/post_process_passthrough.wgsl")
+            , "shaders/post_process_passthrough.wgsl"
+        );
+        let handle = world.resource_mut::<Assets<Shader>>().add(shader);
+        PostProcessPassthroughShader(handle)
+    }
+}
+
+//
+// ベイヤーディザで使う閾値テクスチャを持つリソース
+// 最大サイズ（64x64）の行列を1枚だけ生成し、シェーダー側で必要なサイズの範囲だけを参照する
+//
+#[derive(Resource, Clone, ExtractResource)]
+pub struct BayerTexture(pub Handle<Image>);
+
+impl FromWorld for BayerTexture {
+    fn from_world(world: &mut World) -> Self {
+        let handle = world.resource_mut::<Assets<Image>>().add(bayer_image(BAYER_MAX_ORDER));
+        BayerTexture(handle)
+    }
+}
+
+//
+// ブルーノイズディザで使う閾値テクスチャを持つリソース
+// PostProcessDefaults のサイズとシードから起動時に一度だけ生成する
+// 生成には時間がかかるので別スレッドのタスクで行い、終わるまでは 1x1 の代替画像（閾値 0.5）を使う
+//
+#[derive(Resource, Clone, ExtractResource)]
+pub struct BlueNoiseTexture(pub Handle<Image>);
+
+impl FromWorld for BlueNoiseTexture {
+    fn from_world(world: &mut World) -> Self {
+        let defaults = world.get_resource::<PostProcessDefaults>()
+            .cloned()
+            .unwrap_or_default();
+
+        let task = AsyncComputeTaskPool::get()
+            .spawn(async move { blue_noise_image(defaults.blue_noise_size, defaults.blue_noise_seed) });
+        world.insert_resource(BlueNoiseTask(task));
+
+        let handle = world.resource_mut::<Assets<Image>>().add(blue_noise_placeholder_image());
+        BlueNoiseTexture(handle)
+    }
+}
+
+//
+// 生成中のブルーノイズの閾値テクスチャ（生成が終わったら取り除かれる）
+//
+#[derive(Resource)]
+pub struct BlueNoiseTask(pub Task<Image>);
+
 //
 // レンダリングパイプラインを保持するリソース
 //
@@ -52,14 +118,18 @@ impl FromWorld for PostProcessShader {
 pub struct PostProcessPipeline {
     pub layout: BindGroupLayout
     , pub sampler: Sampler
-    , pub pipeline_id: CachedRenderPipelineId
+    , pub empty_palette: TextureView // パレット未指定のビューに渡す空のパレット
+    , pub empty_depth: TextureView   // 深度プリパスがないビューに渡す 1x1 の深度テクスチャ
     , pub shader_handle: Handle<Shader>
+    , pub passthrough_shader: Handle<Shader> // シェーダーが使えない場合に代わりに使うシェーダー
 }
 impl FromWorld for PostProcessPipeline {
     fn from_world(world: &mut World) -> Self {
-        let (layout, sampler, shader_handle) = {
+        let (layout, sampler, empty_palette, empty_depth, shader_handle, passthrough_shader) = {
             let render_device   = world.resource::<RenderDevice>();
+            let render_queue    = world.resource::<RenderQueue>();
             let shader_resource = world.resource::<PostProcessShader>();
+            let passthrough_resource = world.resource::<PostProcessPassthroughShader>();
             let layout = render_device.create_bind_group_layout(
                 "post_process_bind_group_layout",
                 &BindGroupLayoutEntries::sequential(
@@ -68,49 +138,232 @@ impl FromWorld for PostProcessPipeline {
 
                         texture_2d(TextureSampleType::Float { filterable: true })
                         , sampler(SamplerBindingType::Filtering)
-                        , uniform_buffer::<PostProcessSettings>(true)
+                        , uniform_buffer::<PostProcessUniform>(true)
+                        , texture_2d(TextureSampleType::Float { filterable: false })
+                        , texture_2d(TextureSampleType::Float { filterable: false })
+                        , texture_2d(TextureSampleType::Float { filterable: false })
+                        , texture_2d(TextureSampleType::Float { filterable: false })
+                        , texture_depth_2d()
+                        , texture_2d(TextureSampleType::Float { filterable: false })
                     ),
                 )
             );
 
             let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+            let empty_palette = create_palette_texture(render_device, render_queue, &[])
+                .create_view(&TextureViewDescriptor::default());
+            // 中身は 0（reverse-Z で無限遠）で初期化されるので深度エッジは検出されない
+            let empty_depth = render_device.create_texture(&TextureDescriptor {
+                label: Some("post_process_empty_depth_texture")
+                , size: Extent3d { width: 1, height: 1, depth_or_array_layers: 1 }
+                , mip_level_count: 1
+                , sample_count: 1
+                , dimension: TextureDimension::D2
+                , format: TextureFormat::Depth32Float
+                , usage: TextureUsages::TEXTURE_BINDING
+                , view_formats: &[]
+            }).create_view(&TextureViewDescriptor::default());
             // let shader = world.load_asset("");
-            (layout, sampler, shader_resource.0.clone())
-        };
-
-        let pipeline_id = {
-            let cache = world.resource_mut::<PipelineCache>();
-            cache.queue_render_pipeline(RenderPipelineDescriptor {
-                label: Some("post_process_pipeline".into())
-                , layout: vec![layout.clone()]
-                , vertex: fullscreen_shader_vertex_state()
-                , fragment: Some(FragmentState {
-                    shader: shader_handle.clone()
-                    , shader_defs: vec![]
-                    , entry_point: "fragment".into()
-                    , targets: vec![Some(ColorTargetState {
-                        format: TextureFormat::bevy_default()
-                        , blend: None
-                        , write_mask: ColorWrites::ALL
-                    })]
-                })
-                , primitive: PrimitiveState::default()
-                , depth_stencil: None
-                , multisample: MultisampleState::default()
-                , push_constant_ranges: vec![]
-                , zero_initialize_workgroup_memory: false
-            })
+            (layout, sampler, empty_palette, empty_depth, shader_resource.0.clone(), passthrough_resource.0.clone())
         };
 
         Self {
             layout
             , sampler
-            , pipeline_id
+            , empty_palette
+            , empty_depth
             , shader_handle
+            , passthrough_shader
+        }
+    }
+}
+
+//
+// パイプラインの特殊化のキー
+// カメラごとの設定のうち、ピクセルごとに分岐させずシェーダー定義（#ifdef）で切り替える値を持つ
+// 値を変えるとビューごとに別のキャッシュ済みパイプラインが選ばれる
+//
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct PostProcessPipelineKey {
+    pub shader: AssetId<Shader>        // 使うシェーダー（PostProcessShaderOverride がなければ PostProcessShader）
+    , pub is_enable: bool              // ポストプロセスを適用するかどうか（POST_PROCESS_ENABLE）
+    , pub dither_stage: bool           // ディザの段階を描くかどうか（描かない場合はエッジだけを描く POST_PROCESS_EDGES_ONLY）
+    , pub edge_stage: bool             // エッジの段階を描くかどうか
+    , pub dither: bool                 // ディザを適用するかどうか（DITHER）
+    , pub dither_monochrome: bool      // モノクロディザにするかどうか（DITHER_MONOCHROME）
+    , pub dither_mode: u32             // ディザの閾値マップの種類（DITHER_MODE_*）
+    , pub threshold_map: bool          // ベイヤー行列の代わりに閾値マップを使うかどうか（THRESHOLD_MAP）
+    , pub edge: bool                   // エッジを適用するかどうか（EDGE）
+    , pub edge_luminance: bool         // 輝度の差でエッジを検出するかどうか（EDGE_LUMINANCE）
+    , pub edge_depth: bool             // 深度の差でエッジを検出するかどうか（EDGE_DEPTH）
+    , pub edge_normal: bool            // 法線の角度差でエッジを検出するかどうか（EDGE_NORMAL）
+    , pub edge_kernel: u32             // エッジ検出のカーネル（EDGE_KERNEL_*）
+    , pub non_max_suppression: bool    // 非極大値抑制でエッジを細線化するかどうか（EDGE_NON_MAX_SUPPRESSION）
+    , pub halftone: bool               // ハーフトーンを適用するかどうか（HALFTONE）
+    , pub halftone_cmyk: bool          // CMYK の4版にするかどうか（HALFTONE_CMYK）
+    , pub target_format: TextureFormat // 出力先のビューのテクスチャの形式（HDR のカメラは Rgba16Float）
+    , pub hdr: bool                    // 入力がトーンマッピング前の HDR の値かどうか（HDR）
+}
+
+impl PostProcessPipelineKey {
+    // stages はこのパイプラインで描く段階（PostEffectStack に積まれた段階はスタックの中で別に描く）
+    // threshold_map_ready は閾値マップが GPU に転送済みかどうか（転送されるまではベイヤー行列を使う）
+    pub fn from_settings(
+        shader: AssetId<Shader>
+        , settings: &PostProcessSettings
+        , stages: &[PostProcessStage]
+        , threshold_map_ready: bool
+        , target_format: TextureFormat
+        , hdr: bool
+    ) -> Self {
+        let dither_mode = settings.dither.mode.raw();
+        let dither_stage = stages.contains(&PostProcessStage::Dither);
+        let edge_stage = stages.contains(&PostProcessStage::Edges);
+        Self {
+            shader
+            , is_enable: settings.enabled
+            , dither_stage
+            , edge_stage
+            , dither: dither_stage && settings.dither.enabled
+            , dither_monochrome: settings.dither.monochrome
+            , dither_mode
+            , threshold_map: threshold_map_ready && dither_mode == DITHER_MODE_BAYER
+            , edge: edge_stage && settings.edges.enabled
+            , edge_luminance: settings.edges.luminance
+            , edge_depth: settings.edges.depth.is_some()
+            , edge_normal: settings.edges.normal.is_some()
+            , edge_kernel: settings.edges.kernel.raw()
+            , non_max_suppression: settings.edges.non_max_suppression
+            , halftone: dither_stage && settings.halftone.enabled
+            , halftone_cmyk: settings.halftone.cmyk
+            , target_format
+            , hdr
+        }
+    }
+
+    // 入力をそのまま出力するシェーダーのキー（シェーダー定義はなし）
+    pub fn passthrough(shader: AssetId<Shader>, target_format: TextureFormat) -> Self {
+        Self {
+            shader
+            , is_enable: false
+            , dither_stage: true
+            , edge_stage: true
+            , dither: false
+            , dither_monochrome: false
+            , dither_mode: 0
+            , threshold_map: false
+            , edge: false
+            , edge_luminance: false
+            , edge_depth: false
+            , edge_normal: false
+            , edge_kernel: 0
+            , non_max_suppression: false
+            , halftone: false
+            , halftone_cmyk: false
+            , target_format
+            , hdr: false
+        }
+    }
+
+    // キーに対応するシェーダー定義の一覧
+    // エッジ検出のカーネルは中心差分の場合のみ定義なし
+    pub fn shader_defs(&self) -> Vec<ShaderDefVal> {
+        [
+            (self.is_enable, "POST_PROCESS_ENABLE")
+            , (!self.dither_stage, "POST_PROCESS_EDGES_ONLY")
+            , (self.dither, "DITHER")
+            , (self.dither_monochrome, "DITHER_MONOCHROME")
+            , (self.dither_mode == DITHER_MODE_BLUE_NOISE, "DITHER_BLUE_NOISE")
+            , (self.dither_mode == DITHER_MODE_ERROR_DIFFUSION, "DITHER_ERROR_DIFFUSION")
+            , (self.threshold_map, "THRESHOLD_MAP")
+            , (self.edge, "EDGE")
+            , (self.edge_luminance, "EDGE_LUMINANCE")
+            , (self.edge_depth, "EDGE_DEPTH")
+            , (self.edge_normal, "EDGE_NORMAL")
+            , (self.edge_kernel == EDGE_KERNEL_SOBEL, "EDGE_KERNEL_SOBEL")
+            , (self.edge_kernel == EDGE_KERNEL_SCHARR, "EDGE_KERNEL_SCHARR")
+            , (self.edge_kernel == EDGE_KERNEL_PREWITT, "EDGE_KERNEL_PREWITT")
+            , (self.edge_kernel == EDGE_KERNEL_ROBERTS, "EDGE_KERNEL_ROBERTS")
+            , (self.edge_kernel == EDGE_KERNEL_LAPLACIAN, "EDGE_KERNEL_LAPLACIAN")
+            , (self.non_max_suppression, "EDGE_NON_MAX_SUPPRESSION")
+            , (self.halftone, "HALFTONE")
+            , (self.halftone_cmyk, "HALFTONE_CMYK")
+            , (self.hdr, "HDR")
+        ]
+            .into_iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, name)| name.into())
+            .collect()
+    }
+}
+
+impl SpecializedRenderPipeline for PostProcessPipeline {
+    type Key = PostProcessPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        RenderPipelineDescriptor {
+            label: Some("post_process_pipeline".into())
+            , layout: vec![self.layout.clone()]
+            , vertex: fullscreen_shader_vertex_state()
+            , fragment: Some(FragmentState {
+                shader_defs: key.shader_defs()
+                , shader: Handle::Weak(key.shader) // キーにはハンドルの代わりに ID を持たせている
+                , entry_point: "fragment".into()
+                , targets: vec![Some(ColorTargetState {
+                    format: key.target_format
+                    , blend: None
+                    , write_mask: ColorWrites::ALL
+                })]
+            })
+            , primitive: PrimitiveState::default()
+            , depth_stencil: None
+            , multisample: MultisampleState::default()
+            , push_constant_ranges: vec![]
+            , zero_initialize_workgroup_memory: false
         }
     }
 }
 
+//
+// ビューごとに特殊化されたパイプラインの ID
+//
+#[derive(Component)]
+pub struct ViewPostProcessPipeline {
+    pub id: Option<CachedRenderPipelineId>                       // PostProcessNode で描くパイプライン（すべての段階をスタックで描く場合は None）
+    , pub stage_ids: Vec<(PostProcessStage, CachedRenderPipelineId)> // PostEffectStack に積まれた段階ごとのパイプライン
+    , pub shader: AssetId<Shader>                                // パイプラインのシェーダー（PostProcessShaderStatuses で状態を確認する）
+    , pub fallback_id: CachedRenderPipelineId                    // シェーダーが使えない場合のパイプライン（入力をそのまま出力する）
+}
+
+impl ViewPostProcessPipeline {
+    // このビューのすべてのパイプライン（代わりのパイプラインを除く）
+    pub fn ids(&self) -> impl Iterator<Item = CachedRenderPipelineId> + '_ {
+        self.id.into_iter().chain(self.stage_ids.iter().map(|(_, id)| *id))
+    }
+
+    pub fn stage_id(&self, stage: PostProcessStage) -> Option<CachedRenderPipelineId> {
+        self.stage_ids.iter().find(|(stage_id, _)| *stage_id == stage).map(|(_, id)| *id)
+    }
+
+    //
+    // 描画に使うパイプライン（作成中の場合は None）
+    // シェーダーのコンパイルに失敗したパイプラインと、読み込みに失敗して作成待ちのままのパイプラインは代わりのパイプラインにする
+    // 失敗したかどうかはこのビューのシェーダーの状態で判断する（他のカメラのシェーダーの失敗には影響されない）
+    //
+    pub fn render_pipeline<'w>(&self, id: CachedRenderPipelineId, world: &'w World) -> Option<&'w RenderPipeline> {
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let failed = world.get_resource::<PostProcessShaderStatuses>()
+            .is_some_and(|statuses| statuses.is_failed(self.shader));
+        let pipeline_id = match pipeline_cache.get_render_pipeline_state(id) {
+            CachedPipelineState::Ok(_) => id
+            , CachedPipelineState::Err(_) => self.fallback_id
+            , _ if failed => self.fallback_id
+            , _ => return None
+        };
+        pipeline_cache.get_render_pipeline(pipeline_id)
+    }
+}
+
 //
 // ポストプロセスを識別するためのラベル
 //
@@ -125,38 +378,31 @@ pub struct PostProcessNode;
 impl ViewNode for PostProcessNode {
     type ViewQuery = (
         &'static ViewTarget
-        , &'static PostProcessSettings
-        , &'static DynamicUniformIndex<PostProcessSettings>
+        , &'static DynamicUniformIndex<PostProcessUniform>
+        , &'static ViewPostProcessPipeline
     );
 
     fn run(
         &self,
-        _graph: &mut RenderGraphContext,
+        graph: &mut RenderGraphContext,
         render_context: &mut RenderContext,
-        (view_target, _post_process_settings, settings_index): QueryItem<Self::ViewQuery>,
+        (view_target, settings_index, view_pipeline): QueryItem<Self::ViewQuery>,
         world: &World,
     ) -> Result<(), NodeRunError> {
-        let post_process_pipeline = world.resource::<PostProcessPipeline>();
-        let pipeline_cache = world.resource::<PipelineCache>();
-        let Some(pipeline) = pipeline_cache.get_render_pipeline(post_process_pipeline.pipeline_id)
+        // PostEffectStack に積まれていない段階を描く（すべての段階がスタックに積まれている場合は何もしない）
+        let Some(pipeline) = view_pipeline.id.and_then(|id| view_pipeline.render_pipeline(id, world))
         else {
             return Ok(());
         };
-        let settings_uniforms = world.resource::<ComponentUniforms<PostProcessSettings>>();
-        let Some(settings_binding) = settings_uniforms.uniforms().binding() else {
+
+        // バインドグループは prepare_post_process_bind_groups でビューと入力のテクスチャごとに用意済み
+        // post_process_write は入力と出力を入れ替えるので、使うバインドグループが見つかってから呼ぶ
+        let bind_groups = world.resource::<PostProcessBindGroupCache>();
+        let Some(bind_group) = bind_groups.get(graph.view_entity(), view_target.main_texture_view()) else {
             return Ok(());
         };
-        let post_process = view_target.post_process_write();
-        let bind_group = render_context.render_device().create_bind_group(
-            "post_process_bind_group"
-            , &post_process_pipeline.layout
-            , &BindGroupEntries::sequential((
-                post_process.source
-                , &post_process_pipeline.sampler
-                , settings_binding.clone()
-            ))
-        );
 
+        let post_process = view_target.post_process_write();
         let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
             label: Some("post_process_pass"),
             color_attachments: &[Some(RenderPassColorAttachment {
@@ -170,7 +416,7 @@ impl ViewNode for PostProcessNode {
         });
 
         render_pass.set_render_pipeline(pipeline);
-        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
+        render_pass.set_bind_group(0, bind_group, &[settings_index.index()]);
         render_pass.draw(0..3, 0..1);
 
         Ok(())
@@ -0,0 +1,710 @@
+/*
+  PostProcessSettings を組み立てるための型
+  GPU に渡す値（1=ON 0=OFF の u32 や種類の番号、パディング）は PostProcessUniform への変換時に作られるので、
+  こちらでは bool と列挙型で指定する
+*/
+use std::fmt;
+use bevy::prelude::*;
+use crate::consts::app::*;
+use crate::plugins::structs::components::PostProcessSettings;
+use crate::plugins::structs::threshold_map::ThresholdMap;
+
+//
+// ディザの閾値の作り方
+//
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DitherMode {
+    #[default]
+    Ordered     // ベイヤー行列（閾値マップが指定されている場合は閾値マップ）
+    , BlueNoise // ブルーノイズテクスチャ
+    , ErrorDiffusion {          // 誤差拡散（コンピュートシェーダーの別パスで処理する、WebGL2 では使えない）
+        kernel: DiffusionKernel
+        , serpentine: bool      // 蛇行走査（1行ごとに左右反転）にするかどうか
+    }
+}
+
+impl DitherMode {
+    pub(crate) fn raw(&self) -> u32 {
+        match self {
+            DitherMode::Ordered => DITHER_MODE_BAYER
+            , DitherMode::BlueNoise => DITHER_MODE_BLUE_NOISE
+            , DitherMode::ErrorDiffusion { .. } => DITHER_MODE_ERROR_DIFFUSION
+        }
+    }
+}
+
+//
+// 誤差拡散ディザの拡散カーネル
+//
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DiffusionKernel {
+    #[default]
+    FloydSteinberg
+    , Atkinson          // 誤差の 3/4 だけを拡散する
+    , JarvisJudiceNinke
+}
+
+impl DiffusionKernel {
+    pub(crate) fn raw(&self) -> u32 {
+        match self {
+            DiffusionKernel::FloydSteinberg => DIFFUSION_KERNEL_FLOYD_STEINBERG
+            , DiffusionKernel::Atkinson => DIFFUSION_KERNEL_ATKINSON
+            , DiffusionKernel::JarvisJudiceNinke => DIFFUSION_KERNEL_JARVIS_JUDICE_NINKE
+        }
+    }
+}
+
+//
+// ブレンドするベイヤー行列のサイズの組み合わせ（BAYER_* を OR で組み合わせる）
+//
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BayerLevels(u32);
+
+impl BayerLevels {
+    pub const fn new(levels: u32) -> Self {
+        Self(levels)
+    }
+
+    pub(crate) fn raw(&self) -> u32 {
+        self.0
+    }
+
+    // 生成済みの行列（2x2～2^BAYER_MAX_ORDER）に含まれるものだけを取り出す
+    fn supported(&self) -> u32 {
+        self.0 & (((1 << (BAYER_MAX_ORDER + 1)) - 1) & !1)
+    }
+}
+
+impl Default for BayerLevels {
+    fn default() -> Self {
+        Self(DEFAULT_BAYER_LEVELS)
+    }
+}
+
+//
+// ディザの設定
+// Dither::ordered().scale(2) のように種類を選んでから値を変更する
+//
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Dither {
+    pub(crate) enabled: bool
+    , pub(crate) monochrome: bool
+    , pub(crate) mode: DitherMode
+    , pub(crate) intensity: f32
+    , pub(crate) scale: i32
+    , pub(crate) weight_scaling: f32
+    , pub(crate) bayer_levels: BayerLevels
+}
+
+impl Default for Dither {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_DITHER_ENABLE
+            , monochrome: DEFAULT_DITHER_MONOCHROME
+            , mode: DitherMode::default()
+            , intensity: DEFAULT_DITHER_INTENSITY
+            , scale: DEFAULT_DITHER_SCALE
+            , weight_scaling: DEFAULT_WEIGHT_SCALE
+            , bayer_levels: BayerLevels::default()
+        }
+    }
+}
+
+impl Dither {
+    // ベイヤー行列（または閾値マップ）のディザ
+    pub fn ordered() -> Self {
+        Self { enabled: true, mode: DitherMode::Ordered, ..default() }
+    }
+
+    // ブルーノイズのディザ
+    pub fn blue_noise() -> Self {
+        Self { enabled: true, mode: DitherMode::BlueNoise, ..default() }
+    }
+
+    // 誤差拡散のディザ
+    pub fn error_diffusion(kernel: DiffusionKernel) -> Self {
+        Self { enabled: true, mode: DitherMode::ErrorDiffusion { kernel, serpentine: DEFAULT_DIFFUSION_SERPENTINE }, ..default() }
+    }
+
+    // ディザをかけない
+    pub fn disabled() -> Self {
+        Self { enabled: false, ..default() }
+    }
+
+    // 白黒の2値にするかどうか
+    pub fn monochrome(mut self, monochrome: bool) -> Self {
+        self.monochrome = monochrome;
+        self
+    }
+
+    // ディザをかけるグレースケールの下限（0.0～1.0）
+    pub fn intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    // ディザの1マスの大きさ（ピクセル、1 以上）
+    pub fn scale(mut self, scale: i32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    // 輝度に応じてベイヤー行列をブレンドする際の係数（0 より大きい値）
+    pub fn weight_scaling(mut self, weight_scaling: f32) -> Self {
+        self.weight_scaling = weight_scaling;
+        self
+    }
+
+    // ブレンドするベイヤー行列のサイズ（ベイヤー行列のディザの場合のみ使われる）
+    pub fn bayer_levels(mut self, bayer_levels: BayerLevels) -> Self {
+        self.bayer_levels = bayer_levels;
+        self
+    }
+
+    // 誤差拡散を蛇行走査にするかどうか（誤差拡散のディザの場合のみ使われる）
+    // ※ 蛇行走査は行を並列に処理できないので、大きな画面では誤差拡散のパスが遅くなる
+    pub fn serpentine(mut self, serpentine: bool) -> Self {
+        if let DitherMode::ErrorDiffusion { kernel, .. } = self.mode {
+            self.mode = DitherMode::ErrorDiffusion { kernel, serpentine };
+        }
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn mode(&self) -> DitherMode {
+        self.mode
+    }
+}
+
+//
+// 輝度エッジの検出カーネル
+//
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum EdgeKernel {
+    #[default]
+    CentralDifference // 上下左右の中心差分
+    , Sobel
+    , Scharr          // 回転に対する誤差が小さい
+    , Prewitt
+    , Roberts         // 2x2 の Roberts cross
+    , Laplacian       // 4近傍のラプラシアン（勾配の向きを持たない）
+}
+
+impl EdgeKernel {
+    pub(crate) fn raw(&self) -> u32 {
+        match self {
+            EdgeKernel::CentralDifference => EDGE_KERNEL_CENTRAL_DIFFERENCE
+            , EdgeKernel::Sobel => EDGE_KERNEL_SOBEL
+            , EdgeKernel::Scharr => EDGE_KERNEL_SCHARR
+            , EdgeKernel::Prewitt => EDGE_KERNEL_PREWITT
+            , EdgeKernel::Roberts => EDGE_KERNEL_ROBERTS
+            , EdgeKernel::Laplacian => EDGE_KERNEL_LAPLACIAN
+        }
+    }
+}
+
+//
+// エッジの色の合成方法（いずれもエッジの色の a の割合で元の色と混ぜる）
+//
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum EdgeBlendMode {
+    #[default]
+    Replace    // エッジの色で置き換える
+    , Multiply // 元の色にエッジの色を乗算する（インク風の線）
+    , Additive // 元の色にエッジの色を加算する（光る線）
+    , Darken   // エッジの色は使わず元の色を暗くする
+}
+
+impl EdgeBlendMode {
+    pub(crate) fn raw(&self) -> u32 {
+        match self {
+            EdgeBlendMode::Replace => EDGE_BLEND_REPLACE
+            , EdgeBlendMode::Multiply => EDGE_BLEND_MULTIPLY
+            , EdgeBlendMode::Additive => EDGE_BLEND_ADDITIVE
+            , EdgeBlendMode::Darken => EDGE_BLEND_DARKEN
+        }
+    }
+}
+
+//
+// Outlined のアウトラインの描き方
+// Outlined を付けたメッシュのマスクにだけ使われ、輝度・深度・法線で検出したエッジの描き方は変わらない
+//
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OutlineStyle {
+    #[default]
+    Neighbor    // 周囲のピクセルを探索する（太さは MAX_OUTLINE_WIDTH まで）
+    , JumpFlood // ジャンプフラッディングで距離場を作る（太さの上限なし、境界のアンチエイリアスと光の減衰付き）
+}
+
+//
+// エッジの設定
+// Edges::default() は輝度の差でエッジを検出する
+//
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Edges {
+    pub(crate) enabled: bool
+    , pub(crate) strength: f32
+    , pub(crate) luminance: bool
+    , pub(crate) depth: Option<f32>  // 深度エッジの検出閾値（None の場合は検出しない）
+    , pub(crate) normal: Option<f32> // 法線エッジの検出閾値（度、None の場合は検出しない）
+    , pub(crate) kernel: EdgeKernel
+    , pub(crate) non_max_suppression: bool
+    , pub(crate) thickness: f32
+    , pub(crate) blend_mode: EdgeBlendMode
+    , pub(crate) color: Color
+    , pub(crate) outline_style: OutlineStyle
+    , pub(crate) outline_glow: f32
+}
+
+impl Default for Edges {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_EDGE_ENABLE
+            , strength: DEFAULT_EDGE_STRENGTH
+            , luminance: DEFAULT_EDGE_LUMINANCE
+            , depth: None
+            , normal: None
+            , kernel: EdgeKernel::default()
+            , non_max_suppression: DEFAULT_EDGE_NON_MAX_SUPPRESSION
+            , thickness: DEFAULT_EDGE_THICKNESS
+            , blend_mode: EdgeBlendMode::default()
+            , color: LinearRgba::from_vec4(DEFAULT_EDGE_COLOR).into()
+            , outline_style: OutlineStyle::default()
+            , outline_glow: DEFAULT_OUTLINE_GLOW
+        }
+    }
+}
+
+impl Edges {
+    // エッジを描かない
+    pub fn disabled() -> Self {
+        Self { enabled: false, ..default() }
+    }
+
+    // 輝度エッジの検出閾値（0.0 以上）
+    pub fn strength(mut self, strength: f32) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    // 輝度の差でエッジを検出するかどうか
+    pub fn luminance(mut self, luminance: bool) -> Self {
+        self.luminance = luminance;
+        self
+    }
+
+    // 深度の差でエッジを検出する（中心の深度に対する周囲との深度差の割合、0 より大きい値）
+    // カメラには深度プリパスが追加される
+    pub fn depth(mut self, threshold: f32) -> Self {
+        self.depth = Some(threshold);
+        self
+    }
+
+    // 法線の角度差でエッジを検出する（隣り合う法線のなす角度、0～180 度）
+    // カメラには法線プリパスが追加される
+    pub fn normal(mut self, threshold_degrees: f32) -> Self {
+        self.normal = Some(threshold_degrees);
+        self
+    }
+
+    pub fn kernel(mut self, kernel: EdgeKernel) -> Self {
+        self.kernel = kernel;
+        self
+    }
+
+    // 非極大値抑制でエッジを1ピクセル幅に細線化するかどうか
+    pub fn non_max_suppression(mut self, non_max_suppression: bool) -> Self {
+        self.non_max_suppression = non_max_suppression;
+        self
+    }
+
+    // エッジの太さ（ピクセル、0 より大きく MAX_EDGE_THICKNESS 以下の値）
+    pub fn thickness(mut self, thickness: f32) -> Self {
+        self.thickness = thickness;
+        self
+    }
+
+    pub fn blend_mode(mut self, blend_mode: EdgeBlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    // エッジの色（a は合成の強さ）
+    pub fn color(mut self, color: impl Into<Color>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    pub fn outline_style(mut self, outline_style: OutlineStyle) -> Self {
+        self.outline_style = outline_style;
+        self
+    }
+
+    // アウトラインの外側に広がる光の長さ（ピクセル、0.0 以上、ジャンプフラッディングの場合のみ）
+    pub fn outline_glow(mut self, outline_glow: f32) -> Self {
+        self.outline_glow = outline_glow;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+//
+// ハーフトーンの網点の形
+//
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum HalftoneDot {
+    #[default]
+    Round     // 円
+    , Ellipse // 楕円
+    , Line    // 線
+}
+
+impl HalftoneDot {
+    pub(crate) fn raw(&self) -> u32 {
+        match self {
+            HalftoneDot::Round => HALFTONE_DOT_ROUND
+            , HalftoneDot::Ellipse => HALFTONE_DOT_ELLIPSE
+            , HalftoneDot::Line => HALFTONE_DOT_LINE
+        }
+    }
+}
+
+//
+// ハーフトーンの設定（有効な場合はディザの代わりに適用する）
+//
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Halftone {
+    pub(crate) enabled: bool
+    , pub(crate) cmyk: bool
+    , pub(crate) dot: HalftoneDot
+    , pub(crate) cell_size: f32
+    , pub(crate) angle: f32
+    , pub(crate) cmyk_angles: Vec4
+}
+
+impl Default for Halftone {
+    fn default() -> Self {
+        Self {
+            enabled: DEFAULT_HALFTONE_ENABLE
+            , cmyk: DEFAULT_HALFTONE_CMYK
+            , dot: HalftoneDot::default()
+            , cell_size: DEFAULT_HALFTONE_CELL_SIZE
+            , angle: DEFAULT_HALFTONE_ANGLE
+            , cmyk_angles: DEFAULT_HALFTONE_CMYK_ANGLES
+        }
+    }
+}
+
+impl Halftone {
+    // 単色の1版の網点
+    pub fn monochrome() -> Self {
+        Self { enabled: true, cmyk: false, ..default() }
+    }
+
+    // CMYK の4版の網点
+    pub fn cmyk() -> Self {
+        Self { enabled: true, cmyk: true, ..default() }
+    }
+
+    // ハーフトーンを使わない
+    pub fn disabled() -> Self {
+        Self { enabled: false, ..default() }
+    }
+
+    pub fn dot(mut self, dot: HalftoneDot) -> Self {
+        self.dot = dot;
+        self
+    }
+
+    // 網点1つ分のセルの大きさ（ピクセル、0 より大きい値）
+    pub fn cell_size(mut self, cell_size: f32) -> Self {
+        self.cell_size = cell_size;
+        self
+    }
+
+    // 単色の場合のスクリーン角度（度）
+    pub fn angle(mut self, angle: f32) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    // CMYK それぞれのスクリーン角度（度）
+    pub fn cmyk_angles(mut self, cmyk_angles: Vec4) -> Self {
+        self.cmyk_angles = cmyk_angles;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+//
+// 設定の値が不正な場合のエラー
+//
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PostProcessSettingsError {
+    DitherScale(i32)              // ディザのスケールが 1 未満（シェーダーで 0 除算になる）
+    , DitherIntensity(f32)        // ディザをかけるグレースケールの下限が 0.0～1.0 の範囲外
+    , WeightScaling(f32)          // ベイヤー行列のブレンドの係数が 0 以下
+    , BayerLevels(u32)            // 生成済みのベイヤー行列が1つも含まれていない
+    , EdgeStrength(f32)           // 輝度エッジの検出閾値が負
+    , EdgeThickness(f32)          // エッジの太さが 0 以下か MAX_EDGE_THICKNESS より大きい
+    , DepthThreshold(f32)         // 深度エッジの検出閾値が 0 以下
+    , NormalThreshold(f32)        // 法線エッジの検出閾値が 0～180 度の範囲外
+    , OutlineGlow(f32)            // アウトラインの光の長さが負
+    , HalftoneCellSize(f32)       // 網点のセルの大きさが 0 以下
+    , HalftoneAngle(f32)          // スクリーン角度が有限の値ではない
+    , ErrorDiffusionUnsupported   // WebGL2 のビルドで誤差拡散のディザを指定した（コンピュートシェーダーが使えない）
+    , ThresholdMapUnused(DitherMode) // 閾値マップを使わないディザ（ベイヤー行列以外）に閾値マップを指定した
+}
+
+impl fmt::Display for PostProcessSettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PostProcessSettingsError::DitherScale(value) => write!(f, "dither scale must be 1 or greater, got {value}")
+            , PostProcessSettingsError::DitherIntensity(value) => write!(f, "dither intensity must be within 0.0..=1.0, got {value}")
+            , PostProcessSettingsError::WeightScaling(value) => write!(f, "dither weight scaling must be greater than 0, got {value}")
+            , PostProcessSettingsError::BayerLevels(value) => write!(f, "bayer levels {value:#x} contain no supported matrix size")
+            , PostProcessSettingsError::EdgeStrength(value) => write!(f, "edge strength must not be negative, got {value}")
+            , PostProcessSettingsError::EdgeThickness(value) => write!(f, "edge thickness must be greater than 0 and at most {MAX_EDGE_THICKNESS}, got {value}")
+            , PostProcessSettingsError::DepthThreshold(value) => write!(f, "depth edge threshold must be greater than 0, got {value}")
+            , PostProcessSettingsError::NormalThreshold(value) => write!(f, "normal edge threshold must be within 0..=180 degrees, got {value}")
+            , PostProcessSettingsError::OutlineGlow(value) => write!(f, "outline glow must not be negative, got {value}")
+            , PostProcessSettingsError::HalftoneCellSize(value) => write!(f, "halftone cell size must be greater than 0, got {value}")
+            , PostProcessSettingsError::HalftoneAngle(value) => write!(f, "halftone angle must be finite, got {value}")
+            , PostProcessSettingsError::ErrorDiffusionUnsupported => write!(f, "error diffusion dithering needs compute shaders and is not available with the webgl2 feature")
+            , PostProcessSettingsError::ThresholdMapUnused(mode) => write!(f, "threshold map is only used with ordered dithering, not with {mode:?} dithering")
+        }
+    }
+}
+
+impl std::error::Error for PostProcessSettingsError {}
+
+//
+// PostProcessSettings のビルダー
+//
+// let settings = PostProcessSettings::builder()
+//     .dither(Dither::ordered().scale(2))
+//     .edges(Edges::default().depth(0.1).color(Color::BLACK))
+//     .build()?;
+//
+#[derive(Clone, Default)]
+pub struct PostProcessSettingsBuilder {
+    settings: PostProcessSettings
+}
+
+impl PostProcessSettingsBuilder {
+    pub(crate) fn from_settings(settings: PostProcessSettings) -> Self {
+        Self { settings }
+    }
+
+    // ポストプロセスを適用するかどうか
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.settings.enabled = enabled;
+        self
+    }
+
+    pub fn dither(mut self, dither: Dither) -> Self {
+        self.settings.dither = dither;
+        self
+    }
+
+    pub fn edges(mut self, edges: Edges) -> Self {
+        self.settings.edges = edges;
+        self
+    }
+
+    pub fn halftone(mut self, halftone: Halftone) -> Self {
+        self.settings.halftone = halftone;
+        self
+    }
+
+    // ベイヤー行列の代わりに使う閾値マップ（ブルーノイズと誤差拡散のディザでは使われない）
+    pub fn threshold_map(mut self, threshold_map: Handle<ThresholdMap>) -> Self {
+        self.settings.threshold_map = Some(threshold_map);
+        self
+    }
+
+    // 値を確認して設定を作る
+    pub fn build(self) -> Result<PostProcessSettings, PostProcessSettingsError> {
+        let PostProcessSettings { dither, edges, halftone, .. } = &self.settings;
+
+        if dither.scale < 1 {
+            return Err(PostProcessSettingsError::DitherScale(dither.scale));
+        }
+        if !(0.0..=1.0).contains(&dither.intensity) {
+            return Err(PostProcessSettingsError::DitherIntensity(dither.intensity));
+        }
+        if !(dither.weight_scaling > 0.0 && dither.weight_scaling.is_finite()) {
+            return Err(PostProcessSettingsError::WeightScaling(dither.weight_scaling));
+        }
+        if dither.bayer_levels.supported() == 0 {
+            return Err(PostProcessSettingsError::BayerLevels(dither.bayer_levels.raw()));
+        }
+        #[cfg(feature = "webgl2")]
+        if dither.enabled && matches!(dither.mode, DitherMode::ErrorDiffusion { .. }) {
+            return Err(PostProcessSettingsError::ErrorDiffusionUnsupported);
+        }
+        if self.settings.threshold_map.is_some() && dither.mode != DitherMode::Ordered {
+            return Err(PostProcessSettingsError::ThresholdMapUnused(dither.mode));
+        }
+
+        if !(edges.strength >= 0.0 && edges.strength.is_finite()) {
+            return Err(PostProcessSettingsError::EdgeStrength(edges.strength));
+        }
+        if !(edges.thickness > 0.0 && edges.thickness <= MAX_EDGE_THICKNESS) {
+            return Err(PostProcessSettingsError::EdgeThickness(edges.thickness));
+        }
+        if let Some(threshold) = edges.depth.filter(|threshold| !(*threshold > 0.0 && threshold.is_finite())) {
+            return Err(PostProcessSettingsError::DepthThreshold(threshold));
+        }
+        if let Some(threshold) = edges.normal.filter(|threshold| !(0.0..=180.0).contains(threshold)) {
+            return Err(PostProcessSettingsError::NormalThreshold(threshold));
+        }
+        if !(edges.outline_glow >= 0.0 && edges.outline_glow.is_finite()) {
+            return Err(PostProcessSettingsError::OutlineGlow(edges.outline_glow));
+        }
+
+        if !(halftone.cell_size > 0.0 && halftone.cell_size.is_finite()) {
+            return Err(PostProcessSettingsError::HalftoneCellSize(halftone.cell_size));
+        }
+        if let Some(angle) = [halftone.angle].into_iter().chain(halftone.cmyk_angles.to_array()).find(|angle| !angle.is_finite()) {
+            return Err(PostProcessSettingsError::HalftoneAngle(angle));
+        }
+
+        Ok(self.settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_error(builder: PostProcessSettingsBuilder) -> PostProcessSettingsError {
+        match builder.build() {
+            Err(error) => error
+            , Ok(_) => panic!("expected the settings to be rejected")
+        }
+    }
+
+    #[test]
+    fn valid_settings_build() {
+        let settings = PostProcessSettings::builder()
+            .dither(Dither::ordered().scale(2).intensity(0.5).monochrome(true))
+            .edges(Edges::default().depth(0.1).normal(45.0).thickness(3.0).color(Color::BLACK))
+            .halftone(Halftone::cmyk().cell_size(6.0).angle(15.0))
+            .build()
+            .unwrap();
+        assert_eq!(settings.dither().scale, 2);
+        assert_eq!(settings.edges().depth, Some(0.1));
+        assert!(settings.halftone.cmyk);
+    }
+
+    #[test]
+    fn default_settings_build() {
+        let settings = PostProcessSettings::builder().build().unwrap();
+        assert!(settings.enabled);
+    }
+
+    #[test]
+    fn dither_scale_below_one_is_rejected() {
+        let builder = PostProcessSettings::builder().dither(Dither::ordered().scale(0));
+        assert_eq!(build_error(builder), PostProcessSettingsError::DitherScale(0));
+    }
+
+    #[test]
+    fn dither_intensity_out_of_range_is_rejected() {
+        let builder = PostProcessSettings::builder().dither(Dither::ordered().intensity(1.5));
+        assert_eq!(build_error(builder), PostProcessSettingsError::DitherIntensity(1.5));
+    }
+
+    #[test]
+    fn non_positive_weight_scaling_is_rejected() {
+        let builder = PostProcessSettings::builder().dither(Dither::ordered().weight_scaling(0.0));
+        assert_eq!(build_error(builder), PostProcessSettingsError::WeightScaling(0.0));
+    }
+
+    #[test]
+    fn bayer_levels_without_a_supported_matrix_are_rejected() {
+        let builder = PostProcessSettings::builder().dither(Dither::ordered().bayer_levels(BayerLevels::new(1)));
+        assert_eq!(build_error(builder), PostProcessSettingsError::BayerLevels(1));
+    }
+
+    #[test]
+    fn negative_edge_strength_is_rejected() {
+        let builder = PostProcessSettings::builder().edges(Edges::default().strength(-0.1));
+        assert_eq!(build_error(builder), PostProcessSettingsError::EdgeStrength(-0.1));
+    }
+
+    #[test]
+    fn non_positive_edge_thickness_is_rejected() {
+        let builder = PostProcessSettings::builder().edges(Edges::default().thickness(0.0));
+        assert_eq!(build_error(builder), PostProcessSettingsError::EdgeThickness(0.0));
+    }
+
+    #[test]
+    fn edge_thickness_over_the_limit_is_rejected() {
+        let builder = PostProcessSettings::builder().edges(Edges::default().thickness(MAX_EDGE_THICKNESS + 1.0));
+        assert_eq!(build_error(builder), PostProcessSettingsError::EdgeThickness(MAX_EDGE_THICKNESS + 1.0));
+    }
+
+    #[test]
+    fn non_positive_depth_threshold_is_rejected() {
+        let builder = PostProcessSettings::builder().edges(Edges::default().depth(0.0));
+        assert_eq!(build_error(builder), PostProcessSettingsError::DepthThreshold(0.0));
+    }
+
+    #[test]
+    fn normal_threshold_out_of_range_is_rejected() {
+        let builder = PostProcessSettings::builder().edges(Edges::default().normal(200.0));
+        assert_eq!(build_error(builder), PostProcessSettingsError::NormalThreshold(200.0));
+    }
+
+    #[test]
+    fn negative_outline_glow_is_rejected() {
+        let builder = PostProcessSettings::builder().edges(Edges::default().outline_glow(-1.0));
+        assert_eq!(build_error(builder), PostProcessSettingsError::OutlineGlow(-1.0));
+    }
+
+    #[test]
+    fn non_positive_halftone_cell_size_is_rejected() {
+        let builder = PostProcessSettings::builder().halftone(Halftone::monochrome().cell_size(0.0));
+        assert_eq!(build_error(builder), PostProcessSettingsError::HalftoneCellSize(0.0));
+    }
+
+    #[test]
+    fn non_finite_halftone_angle_is_rejected() {
+        let builder = PostProcessSettings::builder().halftone(Halftone::cmyk().cmyk_angles(Vec4::new(15.0, f32::INFINITY, 45.0, 0.0)));
+        assert_eq!(build_error(builder), PostProcessSettingsError::HalftoneAngle(f32::INFINITY));
+    }
+
+    #[test]
+    fn threshold_map_without_ordered_dithering_is_rejected() {
+        let builder = PostProcessSettings::builder()
+            .dither(Dither::blue_noise())
+            .threshold_map(Handle::default());
+        assert_eq!(build_error(builder), PostProcessSettingsError::ThresholdMapUnused(DitherMode::BlueNoise));
+    }
+
+    #[cfg(feature = "webgl2")]
+    #[test]
+    fn error_diffusion_is_rejected_with_webgl2() {
+        let builder = PostProcessSettings::builder().dither(Dither::error_diffusion(DiffusionKernel::default()));
+        assert_eq!(build_error(builder), PostProcessSettingsError::ErrorDiffusionUnsupported);
+    }
+
+    #[cfg(not(feature = "webgl2"))]
+    #[test]
+    fn error_diffusion_builds_without_webgl2() {
+        let builder = PostProcessSettings::builder().dither(Dither::error_diffusion(DiffusionKernel::default()));
+        assert!(builder.build().is_ok());
+    }
+}
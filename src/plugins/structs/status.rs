@@ -0,0 +1,148 @@
+use std::sync::{Arc, Mutex};
+use bevy::{
+    prelude::*
+    , platform::collections::HashMap
+    , render::extract_resource::ExtractResource
+};
+
+//
+// ポストプロセスのシェーダーの状態
+// 失敗した場合は効果をかけずに入力をそのまま出力するパイプラインに切り替える
+// リソースとしてはすべてのシェーダー（PostProcessShader と各カメラの PostProcessShaderOverride）をまとめた状態を持ち、
+// シェーダーごとの状態は PostProcessShaderStatuses に持つ
+//
+#[derive(Resource, Clone, PartialEq, Eq, Debug, Default)]
+pub enum PostProcessStatus {
+    #[default]
+    Loading          // シェーダーの読み込み中またはパイプラインの作成中
+    , Ready          // 全てのビューのパイプラインが作成済み
+    , Failed(String) // シェーダーの読み込みかコンパイルに失敗した（エラーの内容）
+}
+
+impl PostProcessStatus {
+    pub fn is_failed(&self) -> bool {
+        matches!(self, PostProcessStatus::Failed(_))
+    }
+
+    // 複数の状態をまとめる（失敗 > 読み込み中 > 作成済みの順に優先する）
+    fn merge(self, other: &PostProcessStatus) -> PostProcessStatus {
+        match (self, other) {
+            (failed @ PostProcessStatus::Failed(_), _) => failed
+            , (_, PostProcessStatus::Failed(error)) => PostProcessStatus::Failed(error.clone())
+            , (PostProcessStatus::Loading, _) | (_, PostProcessStatus::Loading) => PostProcessStatus::Loading
+            , _ => PostProcessStatus::Ready
+        }
+    }
+}
+
+//
+// シェーダーごとの状態
+// パイプラインの特殊化のキー（PostProcessPipelineKey）と同じくシェーダーのアセット ID で分ける
+// レンダーワールドにも渡し、失敗したシェーダーを使うビューだけを代わりのパイプラインにする
+//
+#[derive(Resource, Clone, PartialEq, Eq, Debug, Default, ExtractResource)]
+pub struct PostProcessShaderStatuses(pub HashMap<AssetId<Shader>, PostProcessStatus>);
+
+impl PostProcessShaderStatuses {
+    pub fn get(&self, shader: AssetId<Shader>) -> Option<&PostProcessStatus> {
+        self.0.get(&shader)
+    }
+
+    pub fn is_failed(&self, shader: AssetId<Shader>) -> bool {
+        self.get(shader).is_some_and(PostProcessStatus::is_failed)
+    }
+
+    // すべてのシェーダーをまとめた状態（シェーダーがない場合は読み込み中）
+    pub fn summary(&self) -> PostProcessStatus {
+        self.0
+            .values()
+            .fold(None, |summary: Option<PostProcessStatus>, status| Some(match summary {
+                Some(summary) => summary.merge(status)
+                , None => status.clone()
+            }))
+            .unwrap_or_default()
+    }
+
+    // shader の状態に status をまとめる
+    pub fn merge(&mut self, shader: AssetId<Shader>, status: PostProcessStatus) {
+        let merged = match self.0.remove(&shader) {
+            Some(current) => current.merge(&status)
+            , None => status
+        };
+        self.0.insert(shader, merged);
+    }
+}
+
+//
+// ポストプロセスのシェーダーが使えなくなった時に送られるイベント
+//
+#[derive(Event, Clone, Debug)]
+pub struct PostProcessFailed {
+    pub shader: AssetId<Shader> // 使えなくなったシェーダー
+    , pub error: String
+}
+
+//
+// レンダーワールドで確認したシェーダーごとのパイプラインの状態をメインワールドに渡すためのリソース
+// 同じ値をメインワールドとレンダーワールドの両方のリソースとして持つ
+//
+#[derive(Resource, Clone, Default)]
+pub struct PostProcessPipelineStatus(Arc<Mutex<PostProcessShaderStatuses>>);
+
+impl PostProcessPipelineStatus {
+    pub fn set(&self, statuses: PostProcessShaderStatuses) {
+        *self.0.lock().unwrap_or_else(|error| error.into_inner()) = statuses;
+    }
+
+    pub fn get(&self) -> PostProcessShaderStatuses {
+        self.0.lock().unwrap_or_else(|error| error.into_inner()).clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::uuid::Uuid;
+    use super::*;
+
+    const SHADER_A: AssetId<Shader> = AssetId::Uuid { uuid: Uuid::from_u128(1) };
+    const SHADER_B: AssetId<Shader> = AssetId::Uuid { uuid: Uuid::from_u128(2) };
+
+    #[test]
+    fn a_failed_shader_does_not_fail_the_others() {
+        let mut statuses = PostProcessShaderStatuses::default();
+        statuses.merge(SHADER_A, PostProcessStatus::Failed("error".into()));
+        statuses.merge(SHADER_B, PostProcessStatus::Loading);
+
+        assert!(statuses.is_failed(SHADER_A));
+        assert_eq!(statuses.get(SHADER_B), Some(&PostProcessStatus::Loading));
+        assert_eq!(statuses.summary(), PostProcessStatus::Failed("error".into()));
+    }
+
+    #[test]
+    fn views_sharing_a_shader_merge_their_states() {
+        let mut statuses = PostProcessShaderStatuses::default();
+        statuses.merge(SHADER_A, PostProcessStatus::Ready);
+        statuses.merge(SHADER_A, PostProcessStatus::Loading);
+        assert_eq!(statuses.get(SHADER_A), Some(&PostProcessStatus::Loading));
+
+        statuses.merge(SHADER_A, PostProcessStatus::Failed("error".into()));
+        statuses.merge(SHADER_A, PostProcessStatus::Ready);
+        assert!(statuses.is_failed(SHADER_A));
+    }
+
+    #[test]
+    fn summary_is_ready_only_when_every_shader_is_ready() {
+        let mut statuses = PostProcessShaderStatuses::default();
+        assert_eq!(statuses.summary(), PostProcessStatus::Loading);
+
+        statuses.merge(SHADER_A, PostProcessStatus::Ready);
+        statuses.merge(SHADER_B, PostProcessStatus::Loading);
+        assert_eq!(statuses.summary(), PostProcessStatus::Loading);
+
+        // 状態は毎フレーム作り直す
+        let mut statuses = PostProcessShaderStatuses::default();
+        statuses.merge(SHADER_A, PostProcessStatus::Ready);
+        statuses.merge(SHADER_B, PostProcessStatus::Ready);
+        assert_eq!(statuses.summary(), PostProcessStatus::Ready);
+    }
+}
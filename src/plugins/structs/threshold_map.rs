@@ -0,0 +1,137 @@
+use std::fmt;
+use bevy::{
+    prelude::*
+    , asset::{io::Reader, AssetLoader, LoadContext}
+    , ecs::system::{lifetimeless::SRes, SystemParamItem}
+    , render::{
+        render_asset::{PrepareAssetError, RenderAsset}
+        , render_resource::*
+        , renderer::{RenderDevice, RenderQueue}
+    }
+};
+
+//
+// ディザの閾値マップのアセット
+// グレースケール画像の明るさ（0.0～1.0）をそのままピクセルごとの閾値として使う
+// 網点（クラスタードット）、線、ダイヤ型などのパターンを画像で作って差し替えるためのもの
+//
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct ThresholdMap {
+    pub width: u32
+    , pub height: u32
+    , pub values: Vec<f32> // 左上から行ごとに並べた閾値
+}
+
+impl ThresholdMap {
+    //
+    // 画像から閾値マップを作成する（カラー画像は輝度に変換される）
+    //
+    pub fn from_image(image: &image::DynamicImage) -> Self {
+        let luma = image.to_luma32f();
+        Self {
+            width: luma.width()
+            , height: luma.height()
+            , values: luma.into_raw()
+        }
+    }
+}
+
+//
+// 閾値マップの画像の読み込みに失敗した時のエラー
+//
+#[derive(Debug)]
+pub enum ThresholdMapLoaderError {
+    Io(std::io::Error)         // ファイルの読み込みに失敗した
+    , Image(image::ImageError) // 画像のデコードに失敗した
+}
+
+impl fmt::Display for ThresholdMapLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThresholdMapLoaderError::Io(error) => write!(f, "could not read threshold map: {error}")
+            , ThresholdMapLoaderError::Image(error) => write!(f, "could not decode threshold map image: {error}")
+        }
+    }
+}
+
+impl std::error::Error for ThresholdMapLoaderError {}
+
+impl From<std::io::Error> for ThresholdMapLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        ThresholdMapLoaderError::Io(error)
+    }
+}
+
+impl From<image::ImageError> for ThresholdMapLoaderError {
+    fn from(error: image::ImageError) -> Self {
+        ThresholdMapLoaderError::Image(error)
+    }
+}
+
+//
+// 閾値マップのローダー
+// ※ 画像のローダーと拡張子が重なるため extensions には含めていない
+//    asset_server.load::<ThresholdMap>("threshold_maps/xxx.png") のように型を指定して読み込むこと
+//
+#[derive(Default)]
+pub struct ThresholdMapLoader;
+
+impl AssetLoader for ThresholdMapLoader {
+    type Asset = ThresholdMap;
+    type Settings = ();
+    type Error = ThresholdMapLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<ThresholdMap, ThresholdMapLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let image = image::load_from_memory(&bytes)?;
+        Ok(ThresholdMap::from_image(&image))
+    }
+}
+
+//
+// 閾値マップの GPU 側の表現（R32Float のテクスチャ）
+//
+pub struct GpuThresholdMap {
+    pub texture: Texture
+    , pub view: TextureView
+}
+
+impl RenderAsset for GpuThresholdMap {
+    type SourceAsset = ThresholdMap;
+    type Param = (SRes<RenderDevice>, SRes<RenderQueue>);
+
+    fn prepare_asset(
+        threshold_map: ThresholdMap,
+        _asset_id: AssetId<ThresholdMap>,
+        (render_device, render_queue): &mut SystemParamItem<Self::Param>,
+    ) -> Result<Self, PrepareAssetError<ThresholdMap>> {
+        let data = threshold_map.values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<u8>>();
+
+        let texture = render_device.create_texture_with_data(
+            render_queue
+            , &TextureDescriptor {
+                label: Some("post_process_threshold_map_texture")
+                , size: Extent3d { width: threshold_map.width, height: threshold_map.height, depth_or_array_layers: 1 }
+                , mip_level_count: 1
+                , sample_count: 1
+                , dimension: TextureDimension::D2
+                , format: TextureFormat::R32Float
+                , usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST
+                , view_formats: &[]
+            }
+            , TextureDataOrder::LayerMajor
+            , &data
+        );
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        Ok(GpuThresholdMap { texture, view })
+    }
+}